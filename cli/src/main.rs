@@ -1,9 +1,14 @@
 use anyhow::{anyhow, Result};
 use colored::{ColoredString, Colorize};
+use invoice_detective::bolt12_invoice_details::Bolt12InvoiceDetails;
 use invoice_detective::decoder::{decode, resolve_lnurl, DecodedData};
-use invoice_detective::offer_details::{IntroductionNode, OfferDetails};
+use invoice_detective::invoice_request_details::InvoiceRequestDetails;
+use invoice_detective::offer_details::{BlindedPath, IntroductionNode, OfferDetails};
+use invoice_detective::refund_details::RefundDetails;
 use invoice_detective::{
-    InvestigativeFindings, InvoiceDetective, Node, RecipientNode, ServiceKind,
+    ChannelKind, Confidence, ConfidenceLevel, CustodyModel, Fingerprint, Hop,
+    InvestigativeFindings, InvoiceDetails, InvoiceDetective, Node, Provider, RecipientNode,
+    ServiceKind,
 };
 use std::env;
 use thousands::Separable;
@@ -27,7 +32,22 @@ async fn main() -> Result<()> {
             print_findings(findings)
         }
         DecodedData::Refund(refund) => {
-            println!("{refund:?}")
+            let refund_details = RefundDetails::from(refund.clone());
+            print_refund_details(refund_details);
+            let findings = invoice_detective.investigate_refund(refund)?;
+            print_findings(findings)
+        }
+        DecodedData::InvoiceRequest(invoice_request) => {
+            let invoice_request_details = InvoiceRequestDetails::from(invoice_request.clone());
+            print_invoice_request_details(invoice_request_details);
+            let findings = invoice_detective.investigate_invoice_request(invoice_request)?;
+            print_findings(findings)
+        }
+        DecodedData::Bolt12Invoice(invoice) => {
+            let invoice_details = Bolt12InvoiceDetails::from(invoice.clone());
+            print_bolt12_invoice_details(invoice_details);
+            let findings = invoice_detective.investigate_bolt12_invoice(invoice)?;
+            print_findings(findings)
         }
         DecodedData::LnUrl(lnurl) => {
             let invoice = resolve_lnurl(lnurl).await?;
@@ -57,12 +77,82 @@ fn print_offer_details(d: OfferDetails) {
     println!(" Expires at: {}", format_option(&expires_at));
     println!("   Metadata: {}", format_option(&d.metadata));
     println!("Sign pubkey: {}", format_option(&d.signing_pubkey));
-    for (i, path) in d.paths.iter().enumerate() {
+    print_blinded_paths(&d.paths);
+    println!();
+}
+
+fn print_refund_details(d: RefundDetails) {
+    println!("📋 {}", " Details ".reversed());
+    println!("      Chain: {}", d.chain);
+    println!("     Amount: {}", d.amount);
+    println!("   Quantity: {}", format_option(&d.quantity));
+    println!("Description: {}", format_empty(d.description));
+    println!("     Issuer: {}", format_option(&d.issuer));
+    let expires_at = d.expires_at.map(|d| d.to_rfc2822());
+    println!(" Expires at: {}", format_option(&expires_at));
+    println!("   Payer id: {}", d.payer_id);
+    println!(" Payer note: {}", format_option(&d.payer_note));
+    println!("   Metadata: {}", d.payer_metadata);
+    print_blinded_paths(&d.paths);
+    println!();
+}
+
+fn print_invoice_request_details(d: InvoiceRequestDetails) {
+    println!("📋 {}", " Details ".reversed());
+    println!("      Chain: {}", d.chain);
+    println!("     Amount: {}", format_option(&d.amount));
+    println!(" Offer amnt: {}", format_option(&d.offer_amount));
+    println!("   Quantity: {}", format_option(&d.quantity));
+    println!("Description: {}", format_option(&d.description));
+    println!("     Issuer: {}", format_option(&d.issuer));
+    let expires_at = d.expires_at.map(|d| d.to_rfc2822());
+    println!(" Expires at: {}", format_option(&expires_at));
+    println!("Sign pubkey: {}", format_option(&d.signing_pubkey));
+    println!("   Payer id: {}", d.payer_id);
+    println!(" Payer note: {}", format_option(&d.payer_note));
+    println!("   Metadata: {}", d.payer_metadata);
+    print_blinded_paths(&d.paths);
+    println!();
+}
+
+fn print_bolt12_invoice_details(d: Bolt12InvoiceDetails) {
+    println!("📋 {}", " Details ".reversed());
+    println!("      Chain: {}", d.chain);
+    println!("     Amount: {}", d.amount);
+    println!("   Quantity: {}", format_option(&d.quantity));
+    println!("Description: {}", format_option(&d.description));
+    println!("     Issuer: {}", format_option(&d.issuer));
+    let created_at = d.created_at.map(|d| d.to_rfc2822());
+    println!(" Created at: {}", format_option(&created_at));
+    let expires_at = d.expires_at.map(|d| d.to_rfc2822());
+    let expired = if d.is_expired { " (expired)" } else { "" };
+    println!(" Expires at: {}{expired}", format_option(&expires_at));
+    println!("   Pay hash: {}", d.payment_hash);
+    println!("Sign pubkey: {}", d.signing_pubkey);
+    println!("   Payer id: {}", d.payer_id);
+    println!(" Payer note: {}", format_option(&d.payer_note));
+    println!(
+        "   Fallback: {}",
+        format_empty(d.fallback_addresses.join(", "))
+    );
+    for (i, payment_path) in d.payment_paths.iter().enumerate() {
+        let path = &payment_path.path;
         println!(
             "   Paths #{i}: Intro {}",
             format_introduction_node(&path.introduction_node)
         );
         println!("               with blinding {}", path.blinding_point);
+        println!(
+            "               with fees {} msat + {} ppm, CLTV delta {}",
+            payment_path.fee_base_msat,
+            payment_path.fee_proportional_millionths,
+            payment_path.cltv_expiry_delta
+        );
+        println!(
+            "               with HTLC limits {} - {}",
+            format_msat(Some(payment_path.htlc_minimum_msat)),
+            format_msat(Some(payment_path.htlc_maximum_msat))
+        );
         for (i, hop) in path.hops.iter().enumerate() {
             println!("             Hop #{i} {}", hop.node_id);
             println!("                    with data {}", hop.encrypted_payload);
@@ -71,30 +161,134 @@ fn print_offer_details(d: OfferDetails) {
     println!();
 }
 
+fn print_blinded_paths(paths: &[BlindedPath]) {
+    for (i, path) in paths.iter().enumerate() {
+        println!(
+            "   Paths #{i}: Intro {}",
+            format_introduction_node(&path.introduction_node)
+        );
+        println!("               with blinding {}", path.blinding_point);
+        for (i, hop) in path.hops.iter().enumerate() {
+            println!("             Hop #{i} {}", hop.node_id);
+            println!("                    with data {}", hop.encrypted_payload);
+        }
+    }
+}
+
 fn print_findings(findings: InvestigativeFindings) {
     println!("🔎 {}", " Investigative findings ".reversed());
     let recipient = format_recipient_node(&findings.recipient);
     println!("   Recipient: {recipient}");
+    if let Some(provider) = findings.recipient.provider() {
+        print_provider(provider);
+    }
+    println!("  Confidence: {}", format_confidence(&findings.confidence));
+    if let Some(fingerprint) = &findings.fingerprint {
+        println!("    Software: {}", format_fingerprint(fingerprint));
+    }
 
     println!();
     println!("🗃️  {}", " Evidences ".reversed());
     println!("   Pay to {}", format_node_name(&findings.payee));
+    if let Some(announcement) = format_announcement(&findings.payee) {
+        println!("          {announcement}");
+    }
     for hint in findings.route_hints {
-        let hint = hint
+        let nodes = hint
             .iter()
-            .map(format_node_name)
+            .map(|hop| format_node_name(&hop.node))
             .collect::<Vec<_>>()
             .join(" → ");
-        println!("     via {hint}");
+        println!("     via {nodes}");
+        for hop in hint {
+            println!("         {}", format_hop(&hop));
+        }
+    }
+    for (i, path) in findings.blinded_paths.iter().enumerate() {
+        let introduction_node = match &path.introduction_node {
+            Some(node) => format_node_name(node),
+            None => "unresolved introduction node".italic().to_string(),
+        };
+        let recipient = format_recipient_node(&path.recipient);
+        let confidence = format_confidence_level(path.confidence.level);
+        println!("     via blinded path #{i} from {introduction_node}");
+        println!("         suggesting {recipient} ({confidence} confidence)");
     }
 
-    let details = findings.details;
-    let amount = format_msat(details.amount_msat);
-    println!();
+    if !findings.evidences.is_empty() {
+        println!();
+        for (i, evidence) in findings.evidences.iter().enumerate() {
+            println!("   {:>2}. {evidence}", i + 1);
+        }
+    }
+
+    if let Some(details) = findings.details {
+        println!();
+        print_invoice_details(details);
+    }
+}
+
+fn print_provider(provider: &Provider) {
+    println!("     Website: {}", format_option(&provider.website));
+    println!(
+        "     Domains: {}",
+        format_empty(provider.domains.join(", "))
+    );
+    println!("Jurisdiction: {}", format_option(&provider.jurisdiction));
+    println!("     Custody: {}", format_custody_model(provider.custody));
+    for attribution in &provider.attributions {
+        let date = attribution
+            .date
+            .map(|date| format!(" on {date}"))
+            .unwrap_or_default();
+        let window = match (attribution.active_from, attribution.active_until) {
+            (None, None) => String::new(),
+            (from, until) => format!(
+                ", active {} – {}",
+                from.map(|from| from.to_string()).unwrap_or_default(),
+                until.map(|until| until.to_string()).unwrap_or_default()
+            ),
+        };
+        println!(
+            "  Attributed: {} by {}{date}{window}",
+            attribution.node_id, attribution.source
+        );
+    }
+}
+
+fn print_invoice_details(d: InvoiceDetails) {
     println!("📋 {}", " Details ".reversed());
-    println!("    Network: {}", details.network);
-    println!("     Amount: {amount}");
-    println!("Desctiption: {}", details.description.italic());
+    println!("    Network: {}", d.network);
+    println!("     Amount: {}", format_msat(d.amount_msat));
+    match d.description_hash {
+        Some(hash) => println!("  Desc hash: {hash}"),
+        None => println!("Description: {}", d.description.italic()),
+    }
+    println!(" Created at: {}", d.created_at.to_rfc2822());
+    let expires_at = d.expires_at.map(|d| d.to_rfc2822());
+    let expired = if d.is_expired { " (expired)" } else { "" };
+    println!(" Expires at: {}{expired}", format_option(&expires_at));
+    println!("     Expiry: {} seconds", d.expiry_time.as_secs());
+    println!("   Pay hash: {}", d.payment_hash);
+    let payment_secret = if d.has_payment_secret {
+        "present"
+    } else {
+        "missing"
+    };
+    println!(" Pay secret: {payment_secret}");
+    println!("   Min CLTV: {}", d.min_final_cltv_expiry_delta);
+    println!("   Metadata: {}", format_option(&d.payment_metadata));
+    let feature_bits = d
+        .feature_bits
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    println!("   Features: {}", format_empty(feature_bits));
+    println!(
+        "   Fallback: {}",
+        format_empty(d.fallback_addresses.join(", "))
+    );
 }
 
 fn format_option<T: ToString>(value: &Option<T>) -> ColoredString {
@@ -104,6 +298,13 @@ fn format_option<T: ToString>(value: &Option<T>) -> ColoredString {
     }
 }
 
+fn format_empty(value: String) -> ColoredString {
+    match value.is_empty() {
+        true => "empty".italic().dimmed(),
+        false => value.into(),
+    }
+}
+
 fn format_msat(msat: Option<u64>) -> String {
     match msat {
         None => "empty".to_string(),
@@ -131,9 +332,110 @@ fn format_node_name(node: &Node) -> String {
         true => "public",
         false => "private",
     };
+    let stats = format_node_stats(node);
     match &node.alias {
-        Some(alias) => format!("{visibility} node alias:{}", alias.bold()),
-        None => format!("{visibility} node id:{}", node.pubkey.bold()),
+        Some(alias) => format!("{visibility} node alias:{}{stats}", alias.bold()),
+        None => format!("{visibility} node id:{}{stats}", node.pubkey.bold()),
+    }
+}
+
+fn format_node_stats(node: &Node) -> String {
+    if !node.is_announced {
+        return String::new();
+    }
+    let mut stats = vec![format!("{:.2} BTC", node.capacity as f64 / 100_000_000.0)];
+    if node.channels > 0 {
+        stats.push(format!("{} channels", node.channels));
+        stats.push(format!("{} peers", node.peers));
+    }
+    if let Some(blocks) = node.oldest_channel_age {
+        stats.push(format!("oldest channel ~{} days", blocks / 144));
+    }
+    format!(" ({})", stats.join(", "))
+}
+
+fn format_announcement(node: &Node) -> Option<String> {
+    let last_update = node.last_update?;
+    let stale = match node.is_stale {
+        true => " (stale)",
+        false => "",
+    };
+    let addresses = match node.addresses.is_empty() {
+        true => "no addresses".to_string(),
+        false => node.addresses.join(", "),
+    };
+    Some(format!(
+        "updated {}{stale}, {addresses}",
+        last_update.date_naive()
+    ))
+}
+
+fn format_fingerprint(fingerprint: &Fingerprint) -> String {
+    match fingerprint.software {
+        Some(software) => format!(
+            "{} ({} confidence: {})",
+            software.to_string().bold(),
+            format_confidence_level(fingerprint.confidence),
+            fingerprint.signals.join(", ").italic()
+        ),
+        None => "unknown".italic().dimmed().to_string(),
+    }
+}
+
+fn format_hop(hop: &Hop) -> String {
+    let channel = match hop.channel {
+        Some(ChannelKind::Public) => "public channel".green(),
+        Some(ChannelKind::Unannounced) => "unannounced channel".yellow(),
+        Some(ChannelKind::Alias) => "SCID alias".yellow(),
+        None => "channel".normal(),
+    };
+    let age = match hop.channel_age {
+        Some(blocks) => format!(", {blocks} blocks (~{} days) old", blocks / 144),
+        None => String::new(),
+    };
+    let policy = match &hop.public_policy {
+        Some(policy)
+            if policy.base_fee_msat != hop.base_fee_msat
+                || policy.fee_proportional_millionths != hop.fee_proportional_millionths
+                || policy.cltv_expiry_delta != hop.cltv_expiry_delta =>
+        {
+            format!(
+                " (public policy: fee {} msat + {} ppm, CLTV delta {})",
+                policy.base_fee_msat, policy.fee_proportional_millionths, policy.cltv_expiry_delta
+            )
+        }
+        _ => String::new(),
+    };
+    format!(
+        "{channel} {}{age}, fee {} msat + {} ppm, CLTV delta {}{policy}",
+        hop.short_channel_id,
+        hop.base_fee_msat,
+        hop.fee_proportional_millionths,
+        hop.cltv_expiry_delta
+    )
+}
+
+fn format_confidence_level(level: ConfidenceLevel) -> ColoredString {
+    match level {
+        ConfidenceLevel::Low => "low".red(),
+        ConfidenceLevel::Medium => "medium".yellow(),
+        ConfidenceLevel::High => "high".green(),
+    }
+}
+
+fn format_confidence(confidence: &Confidence) -> String {
+    format!(
+        "{} ({})",
+        format_confidence_level(confidence.level),
+        confidence.rationale.italic()
+    )
+}
+
+fn format_custody_model(custody: CustodyModel) -> ColoredString {
+    match custody {
+        CustodyModel::Custodial => "custodial".into(),
+        CustodyModel::NonCustodial => "non-custodial".into(),
+        CustodyModel::Unknown => "unknown".italic().dimmed(),
     }
 }
 
@@ -143,6 +445,8 @@ fn format_service_kind(service: &ServiceKind) -> &str {
         ServiceKind::ConsumerWallet => "Consumer wallet",
         ServiceKind::Exchange => "Exchange",
         ServiceKind::Lsp => "LSP",
+        ServiceKind::SwapService => "Swap service",
+        ServiceKind::EcashMint => "Ecash mint",
     }
 }
 
@@ -153,6 +457,11 @@ fn format_recipient_node(node: &RecipientNode) -> String {
             format_service_kind(&custodian.service),
             custodian.name.bold()
         ),
+        RecipientNode::NonCustodial { id, lsp } if id.is_empty() => format!(
+            "Non-custodial {} {}",
+            format_service_kind(&lsp.service),
+            lsp.name.bold()
+        ),
         RecipientNode::NonCustodial { id, lsp } => format!(
             "Non-custodial {} {} with id:{}",
             format_service_kind(&lsp.service),
@@ -166,6 +475,71 @@ fn format_recipient_node(node: &RecipientNode) -> String {
                 lsp.name.bold()
             )
         }
+        RecipientNode::Swap { swap_service } => format!(
+            "Payment will be swapped to chain via {}",
+            swap_service.name.bold()
+        ),
+        RecipientNode::UnknownSwap { .. } => {
+            "Payment may be swapped to chain via an unknown swap service".to_string()
+        }
+        RecipientNode::PublicRoutingNode { id, alias, lsp } => {
+            let name = alias.as_ref().unwrap_or(id);
+            format!(
+                "Public routing node {} using {} {} as a peer",
+                name.bold(),
+                format_service_kind(&lsp.service),
+                lsp.name.bold()
+            )
+        }
+        RecipientNode::PhantomNode {
+            id,
+            real_nodes,
+            provider,
+        } => {
+            let real_nodes = real_nodes
+                .iter()
+                .map(format_node_name)
+                .collect::<Vec<_>>()
+                .join(", ");
+            let provider = match provider {
+                Some(provider) => format!(" of {}", provider.name.bold()),
+                None => String::new(),
+            };
+            format!(
+                "Phantom node id:{}{provider} served by {real_nodes}",
+                id.bold()
+            )
+        }
+        RecipientNode::EcashGateway {
+            id,
+            pubkey,
+            alias,
+            federation_index,
+        } => {
+            let name = alias.as_ref().unwrap_or(pubkey);
+            format!(
+                "Custodial Ecash mint of federation {} behind Fedimint gateway {} with id:{}",
+                federation_index.to_string().bold(),
+                name.bold(),
+                id.bold()
+            )
+        }
+        RecipientNode::SharedEntryNode { id, pubkey, alias } => {
+            let name = alias.as_ref().unwrap_or(pubkey);
+            format!(
+                "Unknown provider behind node {} ({pubkey}) with id:{}",
+                name.bold(),
+                id.bold()
+            )
+        }
+        RecipientNode::Ambiguous { candidates } => {
+            let candidates = candidates
+                .iter()
+                .map(format_recipient_node)
+                .collect::<Vec<_>>()
+                .join(" or ");
+            format!("Ambiguous: {candidates}")
+        }
         RecipientNode::Unknown => "Unknown".to_string(),
     }
 }
@@ -0,0 +1,327 @@
+use crate::{EdgesMap, NodesMap};
+use std::collections::{HashMap, HashSet};
+
+/// Groups larger than this share a generic alias prefix or a hosting provider IP.
+const MAX_GROUP_SIZE: usize = 20;
+/// Shorter alias prefixes are too generic to identify an operator.
+const MIN_ALIAS_PREFIX_LEN: usize = 4;
+/// Operators connect their own nodes with several parallel channels.
+const MIN_PARALLEL_CHANNELS: u32 = 3;
+/// Share of the channels of the smaller node which go to the other node, in percent.
+const MIN_DEDICATED_CHANNELS_PERCENT: u32 = 80;
+
+/// Groups nodes which probably belong to the same operator.
+///
+/// Nodes are joined if they share an alias prefix (e.g. "ZEBEDEE-1" and "ZEBEDEE-2") or an IP
+/// address, or if most channels of one node go to the other. Nodes of a known provider are
+/// joined, and so is a node with most of its channels going to the nodes of one provider if it
+/// also shares an alias prefix or an IP address with one of them. Clusters with nodes of
+/// different providers are never joined.
+/// Returns cluster ids of nodes in clusters of at least two nodes.
+pub fn cluster(
+    nodes: &NodesMap,
+    edges: &EdgesMap,
+    provider_nodes: &[Vec<String>],
+) -> HashMap<String, u32> {
+    let mut pubkeys = nodes.keys().cloned().collect::<Vec<_>>();
+    pubkeys.sort();
+    let index = pubkeys
+        .iter()
+        .enumerate()
+        .map(|(i, pubkey)| (pubkey.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    let mut clusters = UnionFind::new(pubkeys.len());
+
+    // Nodes of each provider, indexed by the first node of the provider in the graph.
+    let mut providers: HashMap<usize, usize> = HashMap::new();
+    for node_ids in provider_nodes {
+        let members = node_ids
+            .iter()
+            .filter_map(|node_id| index.get(node_id.as_str()).copied())
+            .filter(|node| !providers.contains_key(node))
+            .collect::<Vec<_>>();
+        let Some(&first) = members.first() else {
+            continue;
+        };
+        for &node in &members {
+            clusters.providers[node] = Some(first);
+            providers.insert(node, first);
+        }
+        for &node in &members[1..] {
+            clusters.union(first, node);
+        }
+    }
+
+    let mut prefixes = Vec::with_capacity(pubkeys.len());
+    let mut node_hosts = Vec::with_capacity(pubkeys.len());
+    let mut aliases: HashMap<String, Vec<usize>> = HashMap::new();
+    let mut hosts: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, pubkey) in pubkeys.iter().enumerate() {
+        let node = &nodes[pubkey];
+        let prefix = alias_prefix(&node.alias);
+        if let Some(prefix) = &prefix {
+            aliases.entry(prefix.clone()).or_default().push(i);
+        }
+        let addresses = node
+            .addresses
+            .split(',')
+            .filter_map(host)
+            .collect::<Vec<_>>();
+        for host in &addresses {
+            hosts.entry(host).or_default().push(i);
+        }
+        prefixes.push(prefix);
+        node_hosts.push(addresses);
+    }
+    let groups = aliases.values().chain(hosts.values());
+    for group in groups.filter(|group| group.len() <= MAX_GROUP_SIZE) {
+        for node in &group[1..] {
+            clusters.union(group[0], *node);
+        }
+    }
+
+    // Alias prefixes and hosts of the nodes of each provider, including generic ones.
+    let mut provider_prefixes: HashSet<(usize, &str)> = HashSet::new();
+    let mut provider_hosts: HashSet<(usize, &str)> = HashSet::new();
+    for (&node, &provider) in &providers {
+        if let Some(prefix) = &prefixes[node] {
+            provider_prefixes.insert((provider, prefix));
+        }
+        for host in &node_hosts[node] {
+            provider_hosts.insert((provider, host));
+        }
+    }
+
+    let mut parallel_channels: HashMap<(usize, usize), u32> = HashMap::new();
+    let mut provider_channels: HashMap<(usize, usize), u32> = HashMap::new();
+    for edge in edges.values() {
+        if let (Some(&a), Some(&b)) = (
+            index.get(edge.node1.as_str()),
+            index.get(edge.node2.as_str()),
+        ) {
+            *parallel_channels.entry((a.min(b), a.max(b))).or_default() += 1;
+            for (node, peer) in [(a, b), (b, a)] {
+                if let (None, Some(&provider)) = (providers.get(&node), providers.get(&peer)) {
+                    *provider_channels.entry((node, provider)).or_default() += 1;
+                }
+            }
+        }
+    }
+    for ((a, b), count) in parallel_channels {
+        let channels = nodes[&pubkeys[a]].channels.min(nodes[&pubkeys[b]].channels);
+        if is_dedicated(count, channels) {
+            clusters.union(a, b);
+        }
+    }
+    for ((node, provider), count) in provider_channels {
+        let shares_prefix = prefixes[node]
+            .as_deref()
+            .is_some_and(|prefix| provider_prefixes.contains(&(provider, prefix)));
+        let shares_host = node_hosts[node]
+            .iter()
+            .any(|host| provider_hosts.contains(&(provider, host)));
+        if is_dedicated(count, nodes[&pubkeys[node]].channels) && (shares_prefix || shares_host) {
+            clusters.union(node, provider);
+        }
+    }
+
+    let mut sizes: HashMap<usize, usize> = HashMap::new();
+    for i in 0..pubkeys.len() {
+        *sizes.entry(clusters.find(i)).or_default() += 1;
+    }
+    let mut ids: HashMap<usize, u32> = HashMap::new();
+    let mut result = HashMap::new();
+    for (i, pubkey) in pubkeys.iter().enumerate() {
+        let root = clusters.find(i);
+        if sizes[&root] < 2 {
+            continue;
+        }
+        let next_id = ids.len() as u32 + 1;
+        let id = *ids.entry(root).or_insert(next_id);
+        result.insert(pubkey.clone(), id);
+    }
+    result
+}
+
+/// Whether enough of the channels of a node go to one peer to be run by the same operator.
+fn is_dedicated(count: u32, channels: u32) -> bool {
+    count >= MIN_PARALLEL_CHANNELS && count * 100 >= channels * MIN_DEDICATED_CHANNELS_PERCENT
+}
+
+/// Lowercase alias up to the first separator, e.g. "zebedee" for "ZEBEDEE-klnd1".
+fn alias_prefix(alias: &str) -> Option<String> {
+    let prefix = alias.split(['-', '_']).next()?.to_lowercase();
+    // LND uses a prefix of the pubkey as default alias.
+    let is_default = prefix.chars().all(|c| c.is_ascii_hexdigit());
+    (prefix.chars().count() >= MIN_ALIAS_PREFIX_LEN && !is_default).then_some(prefix)
+}
+
+/// Host of a clearnet `host:port` address.
+fn host(address: &str) -> Option<&str> {
+    let (host, _port) = address.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    (!host.is_empty() && !host.ends_with(".onion")).then_some(host)
+}
+
+struct UnionFind {
+    parents: Vec<usize>,
+    /// Provider of the nodes of a cluster, kept at its root.
+    providers: Vec<Option<usize>>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        Self {
+            parents: (0..size).collect(),
+            providers: vec![None; size],
+        }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        let mut i = i;
+        while self.parents[i] != root {
+            let parent = self.parents[i];
+            self.parents[i] = root;
+            i = parent;
+        }
+        root
+    }
+
+    /// Joins the clusters unless they hold nodes of different providers.
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        let provider = match (self.providers[a], self.providers[b]) {
+            (Some(a), Some(b)) if a != b => return,
+            (a, b) => a.or(b),
+        };
+        if a != b {
+            let (root, child) = (a.min(b), a.max(b));
+            self.parents[child] = root;
+            self.providers[root] = provider;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Edge, Node};
+    use std::collections::HashSet;
+
+    fn node(pubkey: &str, alias: &str, addresses: &str, channels: u32) -> (String, Node) {
+        let node = Node {
+            pubkey: pubkey.to_string(),
+            alias: alias.to_string(),
+            capacity: 0,
+            channels,
+            features: String::new(),
+            color: String::new(),
+            last_update: 0,
+            addresses: addresses.to_string(),
+            peers: HashSet::new(),
+            oldest_channel: None,
+        };
+        (pubkey.to_string(), node)
+    }
+
+    fn edge(node1: &str, node2: &str) -> Edge {
+        Edge {
+            node1: node1.to_string(),
+            node2: node2.to_string(),
+            capacity: 0,
+            node1_policy: None,
+            node2_policy: None,
+        }
+    }
+
+    #[test]
+    fn test_cluster() {
+        let nodes = NodesMap::from([
+            node("02a1", "ZEBEDEE-1", "", 1),
+            node("02a2", "ZEBEDEE-2", "", 1),
+            node("02b1", "Alice", "1.2.3.4:9735", 1),
+            node("02b2", "Bob", "1.2.3.4:9736,bob.onion:9735", 1),
+            node("02c1", "Big", "", 10),
+            node("02c2", "Small", "", 3),
+            node("02d1", "02d1abcdef", "", 1),
+            node("02d2", "02d2abcdef", "", 1),
+        ]);
+        let edges = EdgesMap::from([
+            (1, edge("02c1", "02c2")),
+            (2, edge("02c1", "02c2")),
+            (3, edge("02c2", "02c1")),
+        ]);
+
+        let clusters = cluster(&nodes, &edges, &[]);
+        assert_eq!(clusters.len(), 6);
+        assert_eq!(clusters["02a1"], clusters["02a2"]);
+        assert_eq!(clusters["02b1"], clusters["02b2"]);
+        assert_eq!(clusters["02c1"], clusters["02c2"]);
+        assert_ne!(clusters["02a1"], clusters["02b1"]);
+        assert!(!clusters.contains_key("02d1"));
+    }
+
+    #[test]
+    fn test_cluster_providers() {
+        let mut nodes = NodesMap::from([
+            node("02e1", "Provider", "", 2),
+            node("02e2", "Service", "5.6.7.8:9735", 2),
+            node("02f1", "Client", "5.6.7.8:9736", 3),
+            node("02f2", "Other", "5.6.7.8:9737", 10),
+            node("02f3", "Stranger", "", 3),
+        ]);
+        // A hosting provider IP shared by too many nodes to join them on its own.
+        for i in 0..MAX_GROUP_SIZE {
+            let (pubkey, node) = node(&format!("03{i:02}"), "", "5.6.7.8:9735", 0);
+            nodes.insert(pubkey, node);
+        }
+        let edges = EdgesMap::from([
+            (1, edge("02f1", "02e1")),
+            (2, edge("02f1", "02e2")),
+            (3, edge("02e1", "02f1")),
+            (4, edge("02f2", "02e1")),
+            (5, edge("02f2", "02e2")),
+            (6, edge("02f2", "02e2")),
+            (7, edge("02f3", "02e1")),
+            (8, edge("02f3", "02e2")),
+            (9, edge("02e1", "02f3")),
+        ]);
+        let providers = [vec!["02e1".to_string(), "02e2".to_string()]];
+
+        let clusters = cluster(&nodes, &edges, &providers);
+        assert_eq!(clusters["02e1"], clusters["02e2"]);
+        assert_eq!(clusters["02e1"], clusters["02f1"]);
+        assert!(!clusters.contains_key("02f2"));
+        assert!(!clusters.contains_key("02f3"));
+    }
+
+    #[test]
+    fn test_cluster_distinct_providers() {
+        let nodes = NodesMap::from([
+            node("02a1", "Acme-1", "", 1),
+            node("02a2", "Acme-2", "", 1),
+            node("02a3", "Acme-3", "", 1),
+        ]);
+        let providers = [vec!["02a1".to_string()], vec!["02a2".to_string()]];
+
+        let clusters = cluster(&nodes, &EdgesMap::new(), &providers);
+        assert_eq!(clusters["02a1"], clusters["02a3"]);
+        assert!(!clusters.contains_key("02a2"));
+    }
+
+    #[test]
+    fn test_union_find_long_chain() {
+        let size = 1_000_000;
+        let mut union_find = UnionFind::new(size);
+        // Deep enough to overflow the stack of a recursive find.
+        for i in (1..size).rev() {
+            union_find.parents[i] = i - 1;
+        }
+        assert_eq!(union_find.find(size - 1), 0);
+        assert_eq!(union_find.parents[size - 1], 0);
+    }
+}
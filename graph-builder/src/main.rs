@@ -1,29 +1,66 @@
+mod clustering;
+
 use anyhow::{anyhow, bail, Context, Result};
+use invoice_detective::{ProviderRegistry, GRAPH_SCHEMA};
 use rusqlite::Connection;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     env,
     fs::File,
     io::{self, Read},
 };
 
 const DATABASE_PATH: &str = "./graph.db3";
+const NETWORKS: [&str; 4] = ["bitcoin", "testnet", "signet", "regtest"];
 
 #[derive(Debug)]
 struct Node {
     pubkey: String,
     alias: String,
     capacity: u64,
+    channels: u32,
+    /// Comma separated feature bits.
+    features: String,
+    color: String,
+    last_update: i64,
+    /// Comma separated `host:port` addresses.
+    addresses: String,
+    peers: HashSet<String>,
+    /// Block height of the funding transaction of the oldest channel.
+    oldest_channel: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Policy {
+    fee_base_msat: u64,
+    fee_rate_milli_msat: u64,
+    time_lock_delta: u64,
+    min_htlc: u64,
+    max_htlc_msat: u64,
+    disabled: bool,
+}
+
+#[derive(Debug)]
+struct Edge {
+    node1: String,
+    node2: String,
+    capacity: u64,
+    node1_policy: Option<Policy>,
+    node2_policy: Option<Policy>,
 }
 
 type NodesMap = HashMap<String, Node>;
-type EdgesMap = HashMap<u64, (String, String)>;
+type EdgesMap = HashMap<u64, Edge>;
 
 pub fn main() -> Result<()> {
     let graph_file = env::args()
         .nth(1)
         .ok_or(anyhow!("JSON of Lightning Network graph is required"))?;
-    println!("Reading {graph_file} ...");
+    let network = env::args().nth(2).unwrap_or("bitcoin".to_string());
+    if !NETWORKS.contains(&network.as_str()) {
+        bail!("Unknown network {network}, expected one of {NETWORKS:?}");
+    }
+    println!("Reading {graph_file} of {network} ...");
 
     let graph_file = File::open(graph_file)?;
     let mut buf_reader = io::BufReader::new(graph_file);
@@ -50,68 +87,155 @@ pub fn main() -> Result<()> {
     let edges = process_edges(edges, &mut nodes)?;
 
     println!("Clustering ...");
+    let clusters = clustering::cluster(&nodes, &edges, &provider_nodes(&network));
 
     println!("Dumping to database {DATABASE_PATH} ...");
-    dump(&nodes, &edges)?;
+    dump(&nodes, &edges, &clusters, &network)?;
 
     println!("Done");
     Ok(())
 }
 
-const CREATE_DB: &str = "
+const DROP_TABLES: &str = "
 DROP TABLE IF EXISTS nodes;
-CREATE TABLE nodes (
-    id       INTEGER NOT NULL PRIMARY KEY,
-    pubkey   TEXT NOT NULL,
-    alias    TEXT NOT NULL,
-    capacity INTEGER NOT NULL
-);
-
 DROP TABLE IF EXISTS edges;
-CREATE TABLE edges (
-    scid       INTEGER NOT NULL PRIMARY KEY,
-    left_node  TEXT NOT NULL,
-    right_node TEXT NOT NULL
-);
+DROP TABLE IF EXISTS policies;
+DROP TABLE IF EXISTS graph_info;
 ";
 const INSERT_NODE: &str = "
-INSERT INTO nodes(pubkey, alias, capacity)
-VALUES (?1, ?2, ?3)";
+INSERT INTO nodes(pubkey, alias, capacity, channels, features, color, last_update, addresses,
+    peers, oldest_channel, cluster)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)";
 const INSERT_EDGE: &str = "
-INSERT INTO edges(scid, left_node, right_node)
-VALUES (?1, ?2, ?3)";
+INSERT INTO edges(scid, left_node, right_node, capacity)
+VALUES (?1, ?2, ?3, ?4)";
+const INSERT_POLICY: &str = "
+INSERT INTO policies(scid, node, base_fee_msat, fee_proportional_millionths,
+    cltv_expiry_delta, htlc_minimum_msat, htlc_maximum_msat, disabled)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)";
+const INSERT_NETWORK: &str = "
+INSERT INTO graph_info(key, value)
+VALUES ('network', ?1)";
 
-fn dump(nodes: &NodesMap, edges: &EdgesMap) -> Result<()> {
+fn dump(
+    nodes: &NodesMap,
+    edges: &EdgesMap,
+    clusters: &HashMap<String, u32>,
+    network: &str,
+) -> Result<()> {
     let mut connection = Connection::open(DATABASE_PATH)?;
-    connection.execute_batch(CREATE_DB)?;
+    connection.execute_batch(DROP_TABLES)?;
+    connection.execute_batch(GRAPH_SCHEMA)?;
 
     let transaction = connection.transaction()?;
     {
         let mut statement = transaction.prepare(INSERT_NODE)?;
         for node in nodes.values() {
-            statement.execute([&node.pubkey, &node.alias, &node.capacity.to_string()])?;
+            statement.execute((
+                &node.pubkey,
+                &node.alias,
+                node.capacity as i64,
+                node.channels,
+                &node.features,
+                &node.color,
+                node.last_update,
+                &node.addresses,
+                node.peers.len(),
+                node.oldest_channel,
+                clusters.get(&node.pubkey),
+            ))?;
         }
         let mut statement = transaction.prepare(INSERT_EDGE)?;
-        for (scid, (left_node, right_node)) in edges {
-            statement.execute([scid.to_string(), left_node.clone(), right_node.clone()])?;
+        for (scid, edge) in edges {
+            statement.execute((*scid as i64, &edge.node1, &edge.node2, edge.capacity as i64))?;
+        }
+        let mut statement = transaction.prepare(INSERT_POLICY)?;
+        for (scid, edge) in edges {
+            let policies = [
+                (&edge.node1, &edge.node1_policy),
+                (&edge.node2, &edge.node2_policy),
+            ];
+            for (node, policy) in policies {
+                let Some(policy) = policy else {
+                    continue;
+                };
+                statement.execute((
+                    *scid as i64,
+                    node,
+                    policy.fee_base_msat as i64,
+                    policy.fee_rate_milli_msat as i64,
+                    policy.time_lock_delta as i64,
+                    policy.min_htlc as i64,
+                    policy.max_htlc_msat as i64,
+                    policy.disabled,
+                ))?;
+            }
         }
+        transaction.execute(INSERT_NETWORK, [network])?;
     }
     transaction.commit()?;
 
     Ok(())
 }
 
+/// Node ids of each provider of the network known to invoice-detective.
+fn provider_nodes(network: &str) -> Vec<Vec<String>> {
+    let registry = ProviderRegistry::default();
+    [
+        registry.custodians,
+        registry.wrapping_lsps,
+        registry.lsps,
+        registry.swap_services,
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|provider| provider.network.to_string() == network)
+    .map(|provider| provider.node_ids)
+    .collect()
+}
+
 fn process_nodes(nodes: &json::Array) -> Result<NodesMap> {
     let mut nodes_map = HashMap::with_capacity(nodes.len());
     for node in nodes {
         let node = as_object(node)?;
         let pubkey = get_str(node, "pub_key")?;
         let alias = get_str(node, "alias")?;
+        let color = node
+            .get("color")
+            .and_then(|c| c.as_str())
+            .unwrap_or_default();
+        let last_update = node
+            .get("last_update")
+            .and_then(|l| l.as_i64())
+            .unwrap_or_default();
+        let features = match node.get("features") {
+            Some(features) => as_object(features)?
+                .iter()
+                .map(|(bit, _)| bit)
+                .collect::<Vec<_>>()
+                .join(","),
+            None => String::new(),
+        };
+        let addresses = match node.get("addresses") {
+            Some(addresses) => as_array(addresses)?
+                .iter()
+                .map(|address| Ok(get_str(as_object(address)?, "addr")?.to_string()))
+                .collect::<Result<Vec<_>>>()?
+                .join(","),
+            None => String::new(),
+        };
 
         let node = Node {
             pubkey: pubkey.to_string(),
             alias: alias.to_string(),
             capacity: 0,
+            channels: 0,
+            features,
+            color: color.to_string(),
+            last_update,
+            addresses,
+            peers: HashSet::new(),
+            oldest_channel: None,
         };
 
         nodes_map.insert(pubkey.to_string(), node);
@@ -133,18 +257,63 @@ fn process_edges(edges: &json::Array, nodes: &mut NodesMap) -> Result<EdgesMap>
             .parse()
             .context("channel capacity is not a number")?;
 
-        edges_map.insert(scid, (node1.to_string(), node2.to_string()));
+        let edge_policy = |key| match edge.get(key) {
+            Some(policy) if !policy.is_null() => process_policy(as_object(policy)?).map(Some),
+            _ => Ok(None),
+        };
+        let node1_policy = edge_policy("node1_policy")?;
+        let node2_policy = edge_policy("node2_policy")?;
+
+        edges_map.insert(
+            scid,
+            Edge {
+                node1: node1.to_string(),
+                node2: node2.to_string(),
+                capacity,
+                node1_policy,
+                node2_policy,
+            },
+        );
 
-        if let Some(node) = nodes.get_mut(node1) {
-            node.capacity += capacity;
-        }
-        if let Some(node) = nodes.get_mut(node2) {
-            node.capacity += capacity;
+        let block_height = (scid >> 40) as u32;
+        for (node, peer) in [(node1, node2), (node2, node1)] {
+            if let Some(node) = nodes.get_mut(node) {
+                node.capacity += capacity;
+                node.channels += 1;
+                node.peers.insert(peer.to_string());
+                node.oldest_channel = Some(
+                    node.oldest_channel
+                        .map_or(block_height, |oldest| oldest.min(block_height)),
+                );
+            }
         }
     }
     Ok(edges_map)
 }
 
+fn process_policy(policy: &json::object::Object) -> Result<Policy> {
+    let number = |key| -> Result<u64> {
+        let value = policy.get(key).ok_or(anyhow!(format!("missing {key}")))?;
+        match value.as_u64() {
+            Some(number) => Ok(number),
+            None => get_str(policy, key)?
+                .parse()
+                .context(format!("{key} is not a number")),
+        }
+    };
+    Ok(Policy {
+        fee_base_msat: number("fee_base_msat")?,
+        fee_rate_milli_msat: number("fee_rate_milli_msat")?,
+        time_lock_delta: number("time_lock_delta")?,
+        min_htlc: number("min_htlc")?,
+        max_htlc_msat: number("max_htlc_msat")?,
+        disabled: policy
+            .get("disabled")
+            .and_then(|disabled| disabled.as_bool())
+            .unwrap_or_default(),
+    })
+}
+
 fn as_array(json: &json::JsonValue) -> Result<&json::Array> {
     match json {
         json::JsonValue::Array(array) => Ok(array),
@@ -0,0 +1,87 @@
+use crate::chain_hash::ChainHash;
+use crate::offer_details::{Amount, BlindedPath};
+use bitcoin::hex::DisplayHex;
+use chrono::{DateTime, Utc};
+use lightning::blinded_path::payment::BlindedPaymentPath;
+use lightning::offers::invoice::Bolt12Invoice;
+
+#[derive(Debug)]
+pub struct Bolt12InvoiceDetails {
+    pub chain: String,
+    pub amount: Amount,
+    pub quantity: Option<u64>,
+    pub description: Option<String>,
+    pub issuer: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_expired: bool,
+    pub payment_hash: String,
+    pub signing_pubkey: String,
+    pub payer_id: String,
+    pub payer_note: Option<String>,
+    pub fallback_addresses: Vec<String>,
+    pub payment_paths: Vec<PaymentPath>,
+}
+
+#[derive(Debug)]
+pub struct PaymentPath {
+    pub path: BlindedPath,
+    pub fee_base_msat: u32,
+    pub fee_proportional_millionths: u32,
+    pub cltv_expiry_delta: u16,
+    pub htlc_minimum_msat: u64,
+    pub htlc_maximum_msat: u64,
+}
+
+impl From<&BlindedPaymentPath> for PaymentPath {
+    fn from(path: &BlindedPaymentPath) -> Self {
+        Self {
+            path: BlindedPath::from(path),
+            fee_base_msat: path.payinfo.fee_base_msat,
+            fee_proportional_millionths: path.payinfo.fee_proportional_millionths,
+            cltv_expiry_delta: path.payinfo.cltv_expiry_delta,
+            htlc_minimum_msat: path.payinfo.htlc_minimum_msat,
+            htlc_maximum_msat: path.payinfo.htlc_maximum_msat,
+        }
+    }
+}
+
+impl From<Bolt12Invoice> for Bolt12InvoiceDetails {
+    fn from(invoice: Bolt12Invoice) -> Self {
+        let amount = lightning::offers::offer::Amount::Bitcoin {
+            amount_msats: invoice.amount_msats(),
+        };
+        let created_at = DateTime::from_timestamp(invoice.created_at().as_secs() as i64, 0);
+        let expires_at = invoice
+            .created_at()
+            .checked_add(invoice.relative_expiry())
+            .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, 0));
+        let fallback_addresses = invoice
+            .fallbacks()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let payment_paths = invoice
+            .payment_paths()
+            .iter()
+            .map(PaymentPath::from)
+            .collect();
+
+        Self {
+            chain: ChainHash::from(invoice.chain()).to_string(),
+            amount: Amount::from(amount),
+            quantity: invoice.quantity(),
+            description: invoice.description().map(|s| s.to_string()),
+            issuer: invoice.issuer().map(|s| s.to_string()),
+            created_at,
+            expires_at,
+            is_expired: invoice.is_expired(),
+            payment_hash: invoice.payment_hash().0.as_hex().to_string(),
+            signing_pubkey: invoice.signing_pubkey().to_string(),
+            payer_id: invoice.payer_id().to_string(),
+            payer_note: invoice.payer_note().map(|s| s.to_string()),
+            fallback_addresses,
+            payment_paths,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::error::Result;
+use crate::graph_database::GraphDatabase;
+use crate::provider_registry::ProviderRegistry;
+use crate::recipient::RecipientDecoder;
+use crate::InvoiceDetective;
+use bitcoin::Network;
+use rusqlite::Connection;
+use std::path::PathBuf;
+
+const DEFAULT_DATABASE_PATH: &str = "./graph.db3";
+const NETWORKS: [Network; 4] = [
+    Network::Bitcoin,
+    Network::Testnet,
+    Network::Signet,
+    Network::Regtest,
+];
+
+/// Where the lightning network graph is read from.
+pub enum GraphSource {
+    /// SQLite database file produced by graph-builder.
+    Path(PathBuf),
+    /// Already open connection to a database produced by graph-builder.
+    Connection(Connection),
+    /// Empty graph kept in memory, every node is reported as private.
+    InMemory,
+}
+
+impl Default for GraphSource {
+    fn default() -> Self {
+        Self::Path(PathBuf::from(DEFAULT_DATABASE_PATH))
+    }
+}
+
+impl GraphSource {
+    fn open(self) -> Result<GraphDatabase> {
+        match self {
+            GraphSource::Path(path) => GraphDatabase::open(path),
+            GraphSource::Connection(connection) => GraphDatabase::from_connection(connection),
+            GraphSource::InMemory => GraphDatabase::open_in_memory(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct InvoiceDetectiveBuilder {
+    graph_source: GraphSource,
+    other_graph_sources: Vec<GraphSource>,
+    providers: ProviderRegistry,
+}
+
+impl InvoiceDetectiveBuilder {
+    pub fn graph_source(mut self, graph_source: GraphSource) -> Self {
+        self.graph_source = graph_source;
+        self
+    }
+
+    pub fn database_path<P: Into<PathBuf>>(self, database_path: P) -> Self {
+        self.graph_source(GraphSource::Path(database_path.into()))
+    }
+
+    pub fn connection(self, connection: Connection) -> Self {
+        self.graph_source(GraphSource::Connection(connection))
+    }
+
+    pub fn in_memory_graph(self) -> Self {
+        self.graph_source(GraphSource::InMemory)
+    }
+
+    /// Adds the graph of another network, queried for invoices of that network.
+    ///
+    /// Invoices of networks without a graph are queried against `graph_source`.
+    pub fn add_graph_source(mut self, graph_source: GraphSource) -> Self {
+        self.other_graph_sources.push(graph_source);
+        self
+    }
+
+    pub fn providers(mut self, providers: ProviderRegistry) -> Self {
+        self.providers = providers;
+        self
+    }
+
+    pub fn build(self) -> Result<InvoiceDetective> {
+        let graph_databases = std::iter::once(self.graph_source)
+            .chain(self.other_graph_sources)
+            .map(GraphSource::open)
+            .collect::<Result<_>>()?;
+        let recipient_decoders = NETWORKS
+            .into_iter()
+            .map(|network| {
+                let providers = self.providers.for_network(network);
+                (network, RecipientDecoder::new(providers))
+            })
+            .collect();
+        Ok(InvoiceDetective {
+            graph_databases,
+            recipient_decoders,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Evidence, RecipientNode};
+
+    #[test]
+    fn test_build_in_memory() {
+        let invoice = "lnbc1u1pj62kd6pp557unu8u02cg7nqnsj5rnrgsrzctw7f85g9wr6wu3hhwa5qacmhtqdqqcqzzsxqyz5vqsp5arf47cesn7xyjc7wgq7fl288rczl45j4wql5un4tam8jcuchmh2s9qyyssqmzxkcqk9cpau6fu6zv5n5rz9znuuwwevxz073y8f37yv3qrpp3dpwhruf47206q3rv2st2d7jc2v8nxy7pa6ad7s8rsh9zzq5g33t3qq7d5huu";
+
+        let invoice_detective = InvoiceDetective::builder()
+            .in_memory_graph()
+            .build()
+            .unwrap();
+        let findings = invoice_detective.investigate(invoice).unwrap();
+        assert!(!findings.payee.is_announced);
+        match findings.recipient {
+            RecipientNode::Custodial { custodian } => {
+                assert_eq!(custodian.name, "Wallet of Satoshi")
+            }
+            recipient => panic!("Unexpected recipient: {recipient:?}"),
+        }
+
+        let invoice_detective = InvoiceDetective::builder()
+            .in_memory_graph()
+            .providers(ProviderRegistry::empty())
+            .build()
+            .unwrap();
+        let findings = invoice_detective.investigate(invoice).unwrap();
+        assert_eq!(findings.recipient, RecipientNode::Unknown);
+    }
+
+    #[test]
+    fn test_unknown_swap() {
+        // Aqua swaps to Liquid via Boltz with a min final CLTV delta of 180.
+        let invoice = "lnbc100u1pj6ljjxsp5mk2e0n7vjcuuc752ljnm4zve23a3c3kdq5qxpz5pane0k5dqzjespp53zxjj3sug24tj6wlwlrlfu8k4676s55kj4cr2lw6mntnhk4mh8tsdpz2djkuepqw3hjqnpdgf2yxgrpv3j8yetnwvxqyp2xqcqz959qxpqysgqp43g45mqyk00cj7580dyz8gaevj6dcg7chmjz3gcmdwkcfv0qm5rt4h62fnvxng6nyweapfs7cc5rafne2mqvkun69lxjrus6gvvawspzs6740";
+
+        let invoice_detective = InvoiceDetective::builder()
+            .in_memory_graph()
+            .providers(ProviderRegistry::empty())
+            .build()
+            .unwrap();
+        let findings = invoice_detective.investigate(invoice).unwrap();
+        assert_eq!(
+            findings.recipient,
+            RecipientNode::UnknownSwap {
+                min_final_cltv_expiry_delta: 180
+            }
+        );
+        assert!(findings
+            .evidences
+            .contains(&Evidence::LongFinalCltvExpiryDelta { blocks: 180 }));
+    }
+
+    #[test]
+    fn test_network_scope() {
+        let invoice = "lntb10u1pjkvq6mpp5zszjfrehd5y8sq4w47jegjy5xglw3smcfelfkqud56vtq9c48kmsdqqcqzzsxqyz5vqsp5kgjy259sn4t24er4hawcsr9zl9u7vrkdk7a9kcs9ffury0kf50cq9qyyssqept74lw02kkng3cpzqhyrwt542ct6dtfcz7mtesfggt57r5j7djyz7z5de4cyaupehhwyv7ql6yatqe3e4hvnp2lvpvdwxstpy2rnwqq89p90d";
+        let provider = |network| {
+            format!(
+                r#"
+                version = 1
+
+                [[providers]]
+                name = "Faucet"
+                service = "BusinessWallet"
+                role = "custodian"
+                network = "{network}"
+                node_ids = ["020ec0c6a0c4fe5d8a79928ead294c36234a76f6e0dca896c35413612a3fd8dbf8"]
+            "#
+            )
+        };
+
+        for (network, expected) in [("bitcoin", false), ("signet", false), ("testnet", true)] {
+            let providers = ProviderRegistry::from_toml(&provider(network)).unwrap();
+            let invoice_detective = InvoiceDetective::builder()
+                .in_memory_graph()
+                .providers(providers)
+                .build()
+                .unwrap();
+            let findings = invoice_detective.investigate(invoice).unwrap();
+            assert_eq!(findings.recipient.provider().is_some(), expected);
+            assert!(findings.evidences.contains(&Evidence::GraphMissingNetwork {
+                network: Network::Testnet
+            }));
+        }
+    }
+
+    #[test]
+    fn test_graph_source_per_network() {
+        let invoice = "lntb10u1pjkvq6mpp5zszjfrehd5y8sq4w47jegjy5xglw3smcfelfkqud56vtq9c48kmsdqqcqzzsxqyz5vqsp5kgjy259sn4t24er4hawcsr9zl9u7vrkdk7a9kcs9ffury0kf50cq9qyyssqept74lw02kkng3cpzqhyrwt542ct6dtfcz7mtesfggt57r5j7djyz7z5de4cyaupehhwyv7ql6yatqe3e4hvnp2lvpvdwxstpy2rnwqq89p90d";
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(crate::GRAPH_SCHEMA).unwrap();
+        connection
+            .execute(
+                "INSERT INTO graph_info(key, value) VALUES ('network', 'testnet')",
+                [],
+            )
+            .unwrap();
+
+        let invoice_detective = InvoiceDetective::builder()
+            .in_memory_graph()
+            .add_graph_source(GraphSource::Connection(connection))
+            .build()
+            .unwrap();
+        let findings = invoice_detective.investigate(invoice).unwrap();
+        assert!(!findings
+            .evidences
+            .iter()
+            .any(|evidence| matches!(evidence, Evidence::GraphMissingNetwork { .. })));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::offer_details::ShortChannelId;
+use serde::Serialize;
+
+/// Forwarding policy a node announced for its direction of a channel.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct ChannelPolicy {
+    pub base_fee_msat: u32,
+    pub fee_proportional_millionths: u32,
+    pub cltv_expiry_delta: u16,
+    pub htlc_minimum_msat: u64,
+    pub htlc_maximum_msat: u64,
+    pub is_disabled: bool,
+}
+
+/// An announced channel with the policies of both ends.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct Channel {
+    pub short_channel_id: ShortChannelId,
+    /// Capacity in sats.
+    pub capacity: u64,
+    pub left_node: String,
+    pub right_node: String,
+    pub left_policy: Option<ChannelPolicy>,
+    pub right_policy: Option<ChannelPolicy>,
+}
+
+impl Channel {
+    /// Policy for forwarding from `pubkey` to the other end of the channel.
+    pub fn policy_of(&self, pubkey: &str) -> Option<&ChannelPolicy> {
+        if self.left_node == pubkey {
+            self.left_policy.as_ref()
+        } else if self.right_node == pubkey {
+            self.right_policy.as_ref()
+        } else {
+            None
+        }
+    }
+}
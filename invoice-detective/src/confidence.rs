@@ -0,0 +1,316 @@
+use crate::node::Node;
+use crate::recipient::{Provider, RecipientNode};
+use chrono::NaiveDate;
+use serde::Serialize;
+
+const WELL_FUNDED_CAPACITY_SAT: u64 = 100_000_000;
+/// Capacity of nodes with younger channels is discounted, about 90 days.
+const ESTABLISHED_CHANNEL_AGE_BLOCKS: u32 = 12_960;
+const BLOCKS_PER_DAY: u32 = 144;
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Serialize)]
+pub enum ConfidenceLevel {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct Confidence {
+    pub level: ConfidenceLevel,
+    pub rationale: String,
+}
+
+impl Confidence {
+    fn new(level: ConfidenceLevel, rationale: String) -> Self {
+        Self { level, rationale }
+    }
+}
+
+pub(crate) fn assess(
+    recipient: &RecipientNode,
+    payee: &Node,
+    route_hints: &[Vec<Node>],
+    created_at: Option<NaiveDate>,
+) -> Confidence {
+    match recipient {
+        RecipientNode::Custodial { custodian } => {
+            assess_provider_node(custodian, payee, route_hints, created_at)
+        }
+        RecipientNode::NonCustodialWrapped { lsp } => {
+            assess_provider_node(lsp, payee, route_hints, created_at)
+        }
+        RecipientNode::Swap { swap_service } => {
+            assess_provider_node(swap_service, payee, route_hints, created_at)
+        }
+        RecipientNode::UnknownSwap {
+            min_final_cltv_expiry_delta,
+        } => Confidence::new(
+            ConfidenceLevel::Low,
+            format!(
+                "Payee is unknown, but a min final CLTV delta of {min_final_cltv_expiry_delta} blocks is typical of swaps"
+            ),
+        ),
+        RecipientNode::NonCustodial { lsp, .. } => {
+            assess_route_hints(lsp, payee, route_hints, created_at)
+        }
+        RecipientNode::PublicRoutingNode { lsp, .. } => Confidence::new(
+            ConfidenceLevel::High,
+            format!(
+                "Payee has announced channels and a route hint through {}",
+                lsp.name
+            ),
+        ),
+        RecipientNode::PhantomNode {
+            real_nodes,
+            provider: Some(provider),
+            ..
+        } => Confidence::new(
+            ConfidenceLevel::High,
+            format!(
+                "Phantom payee is served by {} nodes including {}",
+                real_nodes.len(),
+                provider.name
+            ),
+        ),
+        RecipientNode::PhantomNode { real_nodes, .. } => Confidence::new(
+            ConfidenceLevel::Medium,
+            format!(
+                "Route hints match the LDK phantom node pattern with {} real nodes",
+                real_nodes.len()
+            ),
+        ),
+        RecipientNode::EcashGateway { .. } => Confidence::new(
+            ConfidenceLevel::Medium,
+            "Route hint has the federation index SCID and default policy of a Fedimint gateway".to_string(),
+        ),
+        RecipientNode::SharedEntryNode { pubkey, alias, .. } => {
+            let name = alias.as_ref().unwrap_or(pubkey);
+            let rationale = match route_hints.len() {
+                0 | 1 => {
+                    format!(
+                        "Single route hint enters through {name}, which is not a known provider"
+                    )
+                }
+                _ => format!("Entry node {name} is not a known provider"),
+            };
+            Confidence::new(ConfidenceLevel::Low, rationale)
+        }
+        RecipientNode::Ambiguous { .. } => Confidence::new(
+            ConfidenceLevel::Low,
+            "Paths point to different providers".to_string(),
+        ),
+        RecipientNode::Unknown => Confidence::new(
+            ConfidenceLevel::Low,
+            "No known provider matched".to_string(),
+        ),
+    }
+}
+
+fn assess_provider_node(
+    provider: &Provider,
+    payee: &Node,
+    route_hints: &[Vec<Node>],
+    created_at: Option<NaiveDate>,
+) -> Confidence {
+    let name = &provider.name;
+    let Some(node) = std::iter::once(payee)
+        .chain(route_hints.iter().flatten())
+        .find(|node| provider.node_ids.contains(&node.pubkey))
+    else {
+        return Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("Node is attributed to {name} only through its operator cluster"),
+        );
+    };
+    if is_retired(provider, node, created_at) {
+        Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("{name} no longer operated the node when the invoice was created"),
+        )
+    } else if !node.is_announced {
+        Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("Node id of {name} matched, but the node has no announced channels"),
+        )
+    } else if weighted_capacity(node) < WELL_FUNDED_CAPACITY_SAT {
+        Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("{name} node has only {}", format_capacity(node)),
+        )
+    } else {
+        Confidence::new(
+            ConfidenceLevel::High,
+            format!("{name} node has {}", format_capacity(node)),
+        )
+    }
+}
+
+fn assess_route_hints(
+    lsp: &Provider,
+    payee: &Node,
+    route_hints: &[Vec<Node>],
+    created_at: Option<NaiveDate>,
+) -> Confidence {
+    let name = &lsp.name;
+    // Without a known payee node id the payee is the LSP node itself.
+    if payee.is_announced && !lsp.node_ids.contains(&payee.pubkey) {
+        return Confidence::new(
+            ConfidenceLevel::Low,
+            format!("Payee has announced channels, it may not be a client of {name}"),
+        );
+    }
+
+    let lsp_nodes = route_hints
+        .iter()
+        .filter_map(|hint| hint.iter().find(|node| lsp.node_ids.contains(&node.pubkey)))
+        .collect::<Vec<_>>();
+    let total = route_hints.len();
+    let is_operator_node = |node: &Node| {
+        node.operator_nodes
+            .iter()
+            .any(|pubkey| lsp.node_ids.contains(pubkey))
+    };
+    if lsp_nodes.is_empty() && route_hints.iter().flatten().any(is_operator_node) {
+        return Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("Route hint node is attributed to {name} only through its operator cluster"),
+        );
+    }
+    let node = lsp_nodes
+        .iter()
+        .copied()
+        .max_by_key(|node| weighted_capacity(node));
+    match node {
+        _ if lsp_nodes.len() < total => Confidence::new(
+            ConfidenceLevel::Medium,
+            format!(
+                "{} of {total} route hints go through {name}",
+                lsp_nodes.len()
+            ),
+        ),
+        None => Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("No route hints go through {name}"),
+        ),
+        _ if lsp_nodes
+            .iter()
+            .any(|node| is_retired(lsp, node, created_at)) =>
+        {
+            Confidence::new(
+                ConfidenceLevel::Medium,
+                format!("{name} no longer operated a route hint node when the invoice was created"),
+            )
+        }
+        Some(node) if weighted_capacity(node) < WELL_FUNDED_CAPACITY_SAT => Confidence::new(
+            ConfidenceLevel::Medium,
+            format!("{name} node has only {}", format_capacity(node)),
+        ),
+        Some(node) => Confidence::new(
+            ConfidenceLevel::High,
+            format!(
+                "All route hints go through {name} node with {}",
+                format_capacity(node)
+            ),
+        ),
+    }
+}
+
+/// Whether the attribution of the node to the provider expired before the invoice was created.
+fn is_retired(provider: &Provider, node: &Node, created_at: Option<NaiveDate>) -> bool {
+    created_at.is_some_and(|date| !provider.operated(&node.pubkey, date))
+}
+
+/// Capacity of the node, discounted if its oldest channel was opened only recently.
+fn weighted_capacity(node: &Node) -> u64 {
+    match node.oldest_channel_age {
+        Some(age) if age < ESTABLISHED_CHANNEL_AGE_BLOCKS => {
+            (node.capacity as u128 * age as u128 / ESTABLISHED_CHANNEL_AGE_BLOCKS as u128) as u64
+        }
+        _ => node.capacity,
+    }
+}
+
+fn format_capacity(node: &Node) -> String {
+    let capacity = format_btc(node.capacity);
+    match node.oldest_channel_age {
+        Some(age) if age < ESTABLISHED_CHANNEL_AGE_BLOCKS => format!(
+            "{capacity} in announced channels opened within the last {} days",
+            age.div_ceil(BLOCKS_PER_DAY).max(1)
+        ),
+        _ => format!("{capacity} in announced channels"),
+    }
+}
+
+fn format_btc(sat: u64) -> String {
+    format!("{:.2} BTC", sat as f64 / 100_000_000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recipient::{Attribution, ServiceKind};
+
+    fn node(pubkey: &str, capacity: u64) -> Node {
+        Node {
+            pubkey: pubkey.to_string(),
+            is_announced: capacity > 0,
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_assess_route_hints() {
+        let lsp = Provider::new(ServiceKind::ConsumerWallet, "Phoenix", vec!["acinq"]);
+        let recipient = RecipientNode::NonCustodial {
+            id: "payee".to_string(),
+            lsp: lsp.clone(),
+        };
+
+        let hints = [vec![node("acinq", 500_000_000)]];
+        let confidence = assess(&recipient, &node("payee", 0), &hints, None);
+        assert_eq!(confidence.level, ConfidenceLevel::High);
+
+        let hints = [vec![node("acinq", 500_000_000)], vec![node("other", 1)]];
+        let confidence = assess(&recipient, &node("payee", 0), &hints, None);
+        assert_eq!(confidence.level, ConfidenceLevel::Medium);
+
+        let hints = [vec![node("acinq", 500_000_000)]];
+        let confidence = assess(&recipient, &node("payee", 1_000), &hints, None);
+        assert_eq!(confidence.level, ConfidenceLevel::Low);
+
+        let young = Node {
+            oldest_channel_age: Some(1_008),
+            ..node("acinq", 500_000_000)
+        };
+        let confidence = assess(&recipient, &node("payee", 0), &[vec![young]], None);
+        assert_eq!(confidence.level, ConfidenceLevel::Medium);
+        assert_eq!(
+            confidence.rationale,
+            "Phoenix node has only 5.00 BTC in announced channels opened within the last 7 days"
+        );
+    }
+
+    #[test]
+    fn test_assess_retired_node() {
+        let mut custodian = Provider::new(ServiceKind::ConsumerWallet, "Old", vec!["old"]);
+        custodian.attributions.push(Attribution {
+            node_id: "old".to_string(),
+            source: "https://old.example".to_string(),
+            date: None,
+            active_from: None,
+            active_until: NaiveDate::from_ymd_opt(2023, 12, 31),
+        });
+        let recipient = RecipientNode::Custodial { custodian };
+        let payee = node("old", 500_000_000);
+
+        let before = NaiveDate::from_ymd_opt(2023, 6, 1);
+        let confidence = assess(&recipient, &payee, &[], before);
+        assert_eq!(confidence.level, ConfidenceLevel::High);
+
+        let after = NaiveDate::from_ymd_opt(2024, 6, 1);
+        let confidence = assess(&recipient, &payee, &[], after);
+        assert_eq!(confidence.level, ConfidenceLevel::Medium);
+    }
+}
@@ -1,4 +1,7 @@
-use anyhow::{anyhow, bail, Result};
+use crate::error::{Error, Result};
+use bech32::FromBase32;
+use lightning::offers::invoice::Bolt12Invoice;
+use lightning::offers::invoice_request::InvoiceRequest;
 use lightning::offers::offer::Offer;
 use lightning::offers::refund::Refund;
 use lightning_invoice::Bolt11Invoice;
@@ -16,7 +19,9 @@ use std::time::Duration;
 pub enum DecodedData {
     Invoice(Bolt11Invoice),
     Offer(Offer),
-	Refund(Refund),
+    Refund(Refund),
+    InvoiceRequest(InvoiceRequest),
+    Bolt12Invoice(Bolt12Invoice),
     LightningAddress(LightningAddress),
     LnUrl(LnUrl),
 }
@@ -33,32 +38,53 @@ pub fn decode(input: &str) -> Result<DecodedData> {
 
     let decoded_data = if input.contains('@') {
         println!("Decoding as a lightning address");
-        let address = LightningAddress::from_str(input)?;
+        let address = LightningAddress::from_str(input).map_err(|e| Error::Parse(e.to_string()))?;
         DecodedData::LightningAddress(address)
     } else if input.starts_with("lnurl") {
         // TODO: Support LUD-17: Protocol schemes and raw (non bech32-encoded) URLs.
         println!("Decoding as LNURL");
-        let lnurl = LnUrl::from_str(input)?;
+        let lnurl = LnUrl::from_str(input).map_err(|e| Error::Parse(e.to_string()))?;
         DecodedData::LnUrl(lnurl)
     } else if filtered_input.starts_with("lno") {
         println!("Decoding as BOLT12 offer");
-        let offer = Offer::from_str(input).map_err(|e| anyhow!("{e:?}"))?;
+        let offer = Offer::from_str(input)?;
         DecodedData::Offer(offer)
     } else if filtered_input.starts_with("lnr") {
-        println!("Decoding as BOLT12 refund (naked invoice request)");
-		let refund = Refund::from_str(input).map_err(|e| anyhow!("{e:?}"))?;
-		DecodedData::Refund(refund)
+        println!("Decoding as BOLT12 invoice request");
+        let bytes = decode_bech32(&filtered_input, "lnr")?;
+        match InvoiceRequest::try_from(bytes) {
+            Ok(invoice_request) => DecodedData::InvoiceRequest(invoice_request),
+            Err(_) => {
+                println!("Decoding as BOLT12 refund (naked invoice request)");
+                let refund = Refund::from_str(input)?;
+                DecodedData::Refund(refund)
+            }
+        }
+    } else if filtered_input.starts_with("lni") {
+        println!("Decoding as BOLT12 invoice");
+        let bytes = decode_bech32(&filtered_input, "lni")?;
+        let invoice = Bolt12Invoice::try_from(bytes)?;
+        DecodedData::Bolt12Invoice(invoice)
     } else if input.starts_with("ln") {
         println!("Decoding as BOLT11 invoice");
         let invoice = input.parse::<Bolt11Invoice>()?;
         DecodedData::Invoice(invoice)
     } else {
         // TODO: Support BIP-21.
-        bail!("Input is not recognized");
+        return Err(Error::Parse("Input is not recognized".to_string()));
     };
     Ok(decoded_data)
 }
 
+fn decode_bech32(input: &str, expected_hrp: &str) -> Result<Vec<u8>> {
+    let (hrp, data) =
+        bech32::decode_without_checksum(input).map_err(|e| Error::Parse(e.to_string()))?;
+    if hrp != expected_hrp {
+        return Err(Error::Parse(format!("Unexpected bech32 prefix: {hrp}")));
+    }
+    Vec::<u8>::from_base32(&data).map_err(|e| Error::Parse(e.to_string()))
+}
+
 pub async fn resolve_lnurl(lnurl: LnUrl) -> Result<String> {
     println!("Quering {}", lnurl.url);
     let client = reqwest::Client::builder()
@@ -70,13 +96,18 @@ pub async fn resolve_lnurl(lnurl: LnUrl) -> Result<String> {
     println!("Response: {text}");
     print!("Decoding as JSON: ");
     let _ = io::stdout().flush();
-    let response = decode_ln_url_response(&text)?;
+    let response =
+        decode_ln_url_response(&text).map_err(|e| Error::LnurlResponse(e.to_string()))?;
     println!("OK");
 
     let pay = match response {
         LnUrlResponse::LnUrlPayResponse(pay_response) => pay_response,
-        LnUrlResponse::LnUrlWithdrawResponse(_) => bail!("LNURL Withdraw"),
-        LnUrlResponse::LnUrlChannelResponse(_) => bail!("LNURL channel request"),
+        LnUrlResponse::LnUrlWithdrawResponse(_) => {
+            return Err(Error::Unsupported("LNURL withdraw".to_string()))
+        }
+        LnUrlResponse::LnUrlChannelResponse(_) => {
+            return Err(Error::Unsupported("LNURL channel request".to_string()))
+        }
     };
 
     let symbol = if pay.callback.contains('?') { '&' } else { '?' };
@@ -87,11 +118,13 @@ pub async fn resolve_lnurl(lnurl: LnUrl) -> Result<String> {
     println!("Response: {text}");
     print!("Decoding as JSON: ");
     let _ = io::stdout().flush();
-    let json: serde_json::Value = serde_json::from_str(&text)?;
+    let json: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| Error::LnurlResponse(e.to_string()))?;
     println!("OK");
     print!("Decoding as LNURL pay invoice response: ");
     let _ = io::stdout().flush();
-    let reponse: LnURLPayInvoice = serde_json::from_value(json)?;
+    let reponse: LnURLPayInvoice =
+        serde_json::from_value(json).map_err(|e| Error::LnurlResponse(e.to_string()))?;
     println!("OK");
     Ok(reponse.pr)
 }
@@ -111,4 +144,45 @@ mod tests {
         let d = decode("lntb10u1pjkvq6mpp5zszjfrehd5y8sq4w47jegjy5xglw3smcfelfkqud56vtq9c48kmsdqqcqzzsxqyz5vqsp5kgjy259sn4t24er4hawcsr9zl9u7vrkdk7a9kcs9ffury0kf50cq9qyyssqept74lw02kkng3cpzqhyrwt542ct6dtfcz7mtesfggt57r5j7djyz7z5de4cyaupehhwyv7ql6yatqe3e4hvnp2lvpvdwxstpy2rnwqq89p90d").unwrap();
         println!("{d:?}");
     }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(matches!(decode("bitcoin"), Err(Error::Parse(_))));
+        assert!(matches!(decode("lnbc1invalid"), Err(Error::Parse(_))));
+        assert!(matches!(decode("lno1invalid"), Err(Error::Parse(_))));
+    }
+
+    #[test]
+    fn test_decode_invoice_request() {
+        use bech32::ToBase32;
+        use bitcoin::secp256k1::{Keypair, PublicKey, Secp256k1, SecretKey};
+        use lightning::offers::invoice_request::UnsignedInvoiceRequest;
+        use lightning::offers::offer::OfferBuilder;
+        use lightning::util::ser::Writeable;
+
+        let secp_ctx = Secp256k1::new();
+        let recipient = SecretKey::from_slice(&[41; 32]).unwrap();
+        let recipient = PublicKey::from_secret_key(&secp_ctx, &recipient);
+        let payer = Keypair::from_secret_key(&secp_ctx, &SecretKey::from_slice(&[42; 32]).unwrap());
+        let invoice_request = OfferBuilder::new(recipient)
+            .amount_msats(1000)
+            .build()
+            .unwrap()
+            .request_invoice(vec![1; 32], payer.public_key())
+            .unwrap()
+            .build()
+            .unwrap()
+            .sign(|message: &UnsignedInvoiceRequest| {
+                Ok(secp_ctx.sign_schnorr_no_aux_rand(message.as_ref().as_digest(), &payer))
+            })
+            .unwrap();
+        let bytes = invoice_request.encode();
+        let invoice_request = bech32::encode_without_checksum("lnr", bytes.to_base32()).unwrap();
+
+        let d = decode(&invoice_request).unwrap();
+        assert!(matches!(d, DecodedData::InvoiceRequest(_)));
+
+        let d = decode("lnr1qqsqzqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqgpqyqszqg2qpfqyqlgtqssxkl9a9rcyzt8f2twvrclqdlkzaj5plgqr7sav355wux9dfmsn3pv").unwrap();
+        assert!(matches!(d, DecodedData::Refund(_)));
+    }
 }
@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is malformed.
+    Parse(String),
+    /// The input is well-formed, but not supported.
+    Unsupported(String),
+    /// Failed to open or query the graph database.
+    GraphDatabase(rusqlite::Error),
+    /// The short channel id is not found in the graph database.
+    UnknownChannel(String),
+    /// Failed to reach the LNURL service.
+    LnurlHttp(reqwest::Error),
+    /// The LNURL service replied with an unexpected response.
+    LnurlResponse(String),
+    /// Failed to read or parse a provider registry file.
+    ProviderRegistry(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        match self {
+            Error::Parse(message) => write!(f, "Parse error: {message}"),
+            Error::Unsupported(message) => write!(f, "Unsupported input: {message}"),
+            Error::GraphDatabase(e) => write!(f, "Graph database error: {e}"),
+            Error::UnknownChannel(scid) => write!(f, "Channel {scid} is not found in the graph"),
+            Error::LnurlHttp(e) => write!(f, "LNURL request failed: {e}"),
+            Error::LnurlResponse(message) => write!(f, "Invalid LNURL response: {message}"),
+            Error::ProviderRegistry(message) => write!(f, "Invalid provider registry: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::GraphDatabase(e) => Some(e),
+            Error::LnurlHttp(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::GraphDatabase(e)
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::LnurlHttp(e)
+    }
+}
+
+impl From<lightning_invoice::ParseOrSemanticError> for Error {
+    fn from(e: lightning_invoice::ParseOrSemanticError) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+impl From<lightning::offers::parse::Bolt12ParseError> for Error {
+    fn from(e: lightning::offers::parse::Bolt12ParseError) -> Self {
+        Error::Parse(format!("{e:?}"))
+    }
+}
@@ -0,0 +1,233 @@
+use bitcoin::Network;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::fmt::Display;
+
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub enum Evidence {
+    PayeeAnnounced {
+        alias: Option<String>,
+    },
+    PayeeUnannounced,
+    PayeeTorOnly,
+    PayeeStale {
+        last_update: Option<DateTime<Utc>>,
+    },
+    PayeeMajorRoutingNode {
+        channels: u32,
+    },
+    PayeeIsProvider {
+        provider: String,
+        pubkey: String,
+    },
+    PayeeIsUnknown {
+        pubkey: String,
+    },
+    /// A hop of a route hint matches a provider, `position` is 1-based.
+    RouteHintFromProvider {
+        provider: String,
+        pubkey: String,
+        position: usize,
+        hops: usize,
+    },
+    RouteHintFromUnknownNode {
+        pubkey: String,
+    },
+    MultiHopRouteHint {
+        hops: usize,
+    },
+    FederationIndexScid {
+        pubkey: String,
+        scid: String,
+    },
+    FedimintRouteHintPolicy {
+        pubkey: String,
+    },
+    PhantomRouteHints {
+        real_nodes: usize,
+    },
+    PhantomRealNodeIsProvider {
+        provider: String,
+        pubkey: String,
+    },
+    SharedEntryNode {
+        pubkey: String,
+        alias: Option<String>,
+        hints: usize,
+    },
+    /// The node is in the operator cluster of a provider node.
+    SameOperatorAsProvider {
+        provider: String,
+        pubkey: String,
+        provider_node: String,
+    },
+    /// The node was matched against an attribution which ended before the invoice was created.
+    RetiredProviderNode {
+        provider: String,
+        pubkey: String,
+        until: Option<NaiveDate>,
+    },
+    GraphMissingNetwork {
+        network: Network,
+    },
+    IntroductionNodeIsProvider {
+        provider: String,
+        pubkey: String,
+    },
+    BlindedPathFromProvider {
+        provider: String,
+        pubkey: String,
+    },
+    IntroductionNodeIsUnknown {
+        pubkey: String,
+    },
+    IntroductionNodeUnresolved,
+    LongFinalCltvExpiryDelta {
+        blocks: u64,
+    },
+}
+
+impl Display for Evidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Evidence::PayeeAnnounced { alias: Some(alias) } => {
+                write!(f, "payee has announced channels as {alias}")
+            }
+            Evidence::PayeeAnnounced { alias: None } => write!(f, "payee has announced channels"),
+            Evidence::PayeeUnannounced => write!(f, "payee has no announced channels"),
+            Evidence::PayeeTorOnly => write!(f, "payee advertises only Tor addresses"),
+            Evidence::PayeeStale {
+                last_update: Some(last_update),
+            } => write!(
+                f,
+                "payee did not refresh its announcement since {}",
+                last_update.date_naive()
+            ),
+            Evidence::PayeeStale { last_update: None } => {
+                write!(f, "payee did not refresh its announcement")
+            }
+            Evidence::PayeeMajorRoutingNode { channels } => {
+                write!(f, "payee is a major routing node with {channels} channels")
+            }
+            Evidence::PayeeIsProvider { provider, pubkey } => write!(
+                f,
+                "payee pubkey matches {provider} node {}",
+                shorten(pubkey)
+            ),
+            Evidence::PayeeIsUnknown { pubkey } => write!(
+                f,
+                "payee pubkey {} matches no known provider",
+                shorten(pubkey)
+            ),
+            Evidence::RouteHintFromProvider {
+                provider,
+                pubkey,
+                hops: 1,
+                ..
+            } => write!(
+                f,
+                "single-hop route hint from {provider} node {}",
+                shorten(pubkey)
+            ),
+            Evidence::RouteHintFromProvider {
+                provider,
+                pubkey,
+                position,
+                hops,
+            } => write!(
+                f,
+                "hop {position} of {hops}-hop route hint is {provider} node {}",
+                shorten(pubkey)
+            ),
+            Evidence::RouteHintFromUnknownNode { pubkey } => write!(
+                f,
+                "single-hop route hint from unknown node {}",
+                shorten(pubkey)
+            ),
+            Evidence::MultiHopRouteHint { hops } => {
+                write!(f, "route hint with {hops} hops through unknown nodes")
+            }
+            Evidence::SameOperatorAsProvider {
+                provider,
+                pubkey,
+                provider_node,
+            } => write!(
+                f,
+                "node {} is probably operated by {provider} like node {}",
+                shorten(pubkey),
+                shorten(provider_node)
+            ),
+            Evidence::RetiredProviderNode {
+                provider,
+                pubkey,
+                until,
+            } => {
+                write!(f, "node {} of {provider} was retired", shorten(pubkey))?;
+                match until {
+                    Some(until) => write!(f, " after {until}"),
+                    None => Ok(()),
+                }
+            }
+            Evidence::GraphMissingNetwork { network } => {
+                write!(f, "graph database has no data for {network}")
+            }
+            Evidence::FederationIndexScid { pubkey, scid } => write!(
+                f,
+                "route hint from node {} uses federation index {scid} instead of a channel",
+                shorten(pubkey)
+            ),
+            Evidence::FedimintRouteHintPolicy { pubkey } => write!(
+                f,
+                "route hint from node {} has the default fees and CLTV delta of a Fedimint gateway",
+                shorten(pubkey)
+            ),
+            Evidence::PhantomRouteHints { real_nodes } => write!(
+                f,
+                "route hints end in free hops from {real_nodes} nodes to a phantom payee"
+            ),
+            Evidence::PhantomRealNodeIsProvider { provider, pubkey } => write!(
+                f,
+                "phantom payee is served by {provider} node {}",
+                shorten(pubkey)
+            ),
+            Evidence::SharedEntryNode {
+                pubkey,
+                alias,
+                hints,
+            } => {
+                let name = alias.clone().unwrap_or_else(|| shorten(pubkey));
+                write!(f, "all {hints} route hints enter through node {name}")
+            }
+            Evidence::IntroductionNodeIsProvider { provider, pubkey } => write!(
+                f,
+                "blinded path introduction node matches {provider} node {}",
+                shorten(pubkey)
+            ),
+            Evidence::BlindedPathFromProvider { provider, pubkey } => {
+                write!(f, "blinded path from {provider} node {}", shorten(pubkey))
+            }
+            Evidence::IntroductionNodeIsUnknown { pubkey } => write!(
+                f,
+                "blinded path introduction node {} matches no known provider",
+                shorten(pubkey)
+            ),
+            Evidence::LongFinalCltvExpiryDelta { blocks } => write!(
+                f,
+                "min final CLTV delta of {blocks} blocks leaves time for an on-chain lockup"
+            ),
+            Evidence::IntroductionNodeUnresolved => {
+                write!(
+                    f,
+                    "blinded path introduction node is not found in the graph"
+                )
+            }
+        }
+    }
+}
+
+fn shorten(pubkey: &str) -> String {
+    match pubkey.get(..6) {
+        Some(prefix) if pubkey.len() > 6 => format!("{prefix}…"),
+        _ => pubkey.to_string(),
+    }
+}
@@ -0,0 +1,261 @@
+use crate::confidence::ConfidenceLevel;
+use crate::invoice_details::InvoiceDetails;
+use lightning_invoice::{Bolt11Invoice, TaggedField};
+use serde::Serialize;
+use std::fmt::Display;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize)]
+pub enum Software {
+    Lnd,
+    CoreLightning,
+    Ldk,
+    Eclair,
+    Phoenix,
+    BreezSdk,
+}
+
+impl Display for Software {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let name = match self {
+            Software::Lnd => "LND",
+            Software::CoreLightning => "Core Lightning",
+            Software::Ldk => "LDK",
+            Software::Eclair => "Eclair",
+            Software::Phoenix => "Phoenix",
+            Software::BreezSdk => "Breez SDK",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Estimate of the software which generated an invoice.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct Fingerprint {
+    /// `None` if no signal matched or the signals are inconclusive.
+    pub software: Option<Software>,
+    pub confidence: ConfidenceLevel,
+    /// Signals in favour of `software`.
+    pub signals: Vec<String>,
+}
+
+struct Signal {
+    software: Software,
+    weight: u32,
+    description: &'static str,
+}
+
+/// SHA-256 of an empty string.
+const EMPTY_DESCRIPTION_HASH: &str =
+    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Matches the encoding of the invoice against defaults of known implementations.
+///
+/// Signals were collected from invoices of wallets listed in `sample/invoices.csv`.
+/// Weights reflect how specific a signal is:
+/// 3 for an encoding trait seen only in invoices of one implementation,
+/// 2 for a trait shared by two implementations or which others could easily adopt,
+/// 1 for a configurable default or a convention shared by several implementations.
+pub(crate) fn fingerprint(invoice: &Bolt11Invoice, details: &InvoiceDetails) -> Fingerprint {
+    let mut signals = Vec::new();
+    let mut signal = |software, weight, description| {
+        signals.push(Signal {
+            software,
+            weight,
+            description,
+        })
+    };
+
+    let fields = field_tags(invoice);
+    if fields.starts_with("pdcx") || fields.starts_with("phcx") {
+        signal(
+            Software::Lnd,
+            3,
+            "fields ordered as payment hash, description, CLTV, expiry",
+        );
+    }
+    if fields.contains('n') {
+        // Only LDK wallets in the sample encode it, but any implementation may.
+        signal(Software::Ldk, 2, "explicit payee pubkey field");
+    } else if fields.starts_with("sp") {
+        signal(
+            Software::CoreLightning,
+            2,
+            "payment secret field goes first",
+        );
+    }
+    if fields.starts_with("pcs9") {
+        let description = "fields ordered as payment hash, CLTV, secret, features";
+        signal(Software::Eclair, 2, description);
+        signal(Software::Phoenix, 2, description);
+    }
+    if fields.starts_with("dpxs9") {
+        let description = "fields ordered as description, payment hash, expiry, secret";
+        signal(Software::BreezSdk, 3, description);
+    }
+    if let (Some(description), Some(features)) = (fields.find('d'), fields.find('9')) {
+        if description > features {
+            let description = "description field goes after features";
+            signal(Software::Eclair, 1, description);
+            signal(Software::Phoenix, 1, description);
+        }
+    }
+
+    if fields.contains('d') && details.description.is_empty() {
+        let description = "empty description field";
+        signal(Software::Lnd, 1, description);
+        signal(Software::Ldk, 1, description);
+        signal(Software::Eclair, 1, description);
+        signal(Software::Phoenix, 1, description);
+    }
+    if details.description_hash.as_deref() == Some(EMPTY_DESCRIPTION_HASH) {
+        signal(
+            Software::CoreLightning,
+            1,
+            "description hash of an empty string",
+        );
+    }
+
+    let features = &details.feature_bits;
+    if features.contains(&9) {
+        signal(Software::Lnd, 2, "variable length onion is optional");
+    }
+    if features.contains(&149) {
+        signal(Software::Phoenix, 3, "trampoline routing feature");
+    }
+    if features.contains(&49) {
+        let description = "payment metadata feature";
+        signal(Software::Eclair, 1, description);
+        signal(Software::Phoenix, 1, description);
+    }
+    if features.contains(&25) {
+        signal(Software::CoreLightning, 1, "route blinding feature");
+    }
+    if features == &[8, 14] {
+        signal(
+            Software::BreezSdk,
+            1,
+            "only onion and payment secret features",
+        );
+    }
+
+    match details.min_final_cltv_expiry_delta {
+        80 => signal(Software::Lnd, 1, "min final CLTV delta of 80"),
+        24 => signal(Software::Ldk, 1, "min final CLTV delta of 24"),
+        18 => {
+            signal(Software::CoreLightning, 1, "min final CLTV delta of 18");
+            signal(Software::Phoenix, 1, "min final CLTV delta of 18");
+        }
+        144 => signal(Software::BreezSdk, 1, "min final CLTV delta of 144"),
+        _ => (),
+    }
+
+    let expiry = invoice.tagged_fields().find_map(|field| match field {
+        TaggedField::ExpiryTime(expiry) => Some(expiry.as_seconds()),
+        _ => None,
+    });
+    match expiry {
+        Some(86_400) => signal(Software::Lnd, 1, "expiry of 1 day"),
+        Some(604_800) => {
+            signal(Software::CoreLightning, 1, "expiry of 1 week");
+            signal(Software::Phoenix, 1, "expiry of 1 week");
+        }
+        _ => (),
+    }
+
+    evaluate(signals)
+}
+
+fn evaluate(signals: Vec<Signal>) -> Fingerprint {
+    let mut scores: Vec<(Software, u32)> = Vec::new();
+    for signal in &signals {
+        match scores.iter_mut().find(|(s, _)| *s == signal.software) {
+            Some((_, score)) => *score += signal.weight,
+            None => scores.push((signal.software, signal.weight)),
+        }
+    }
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+
+    let (software, score) = match scores.as_slice() {
+        [] => return inconclusive(),
+        [(_, first), (_, second), ..] if first == second => return inconclusive(),
+        [(software, score), ..] => (*software, *score),
+    };
+    let confidence = match score {
+        5.. => ConfidenceLevel::High,
+        3.. => ConfidenceLevel::Medium,
+        _ => ConfidenceLevel::Low,
+    };
+    let signals = signals
+        .into_iter()
+        .filter(|signal| signal.software == software)
+        .map(|signal| signal.description.to_string())
+        .collect();
+    Fingerprint {
+        software: Some(software),
+        confidence,
+        signals,
+    }
+}
+
+fn inconclusive() -> Fingerprint {
+    Fingerprint {
+        software: None,
+        confidence: ConfidenceLevel::Low,
+        signals: Vec::new(),
+    }
+}
+
+/// Tags of known fields in the order of encoding, e.g. "pdcxs9".
+fn field_tags(invoice: &Bolt11Invoice) -> String {
+    invoice
+        .tagged_fields()
+        .map(|field| match field {
+            TaggedField::PaymentHash(_) => 'p',
+            TaggedField::Description(_) => 'd',
+            TaggedField::PayeePubKey(_) => 'n',
+            TaggedField::DescriptionHash(_) => 'h',
+            TaggedField::ExpiryTime(_) => 'x',
+            TaggedField::MinFinalCltvExpiryDelta(_) => 'c',
+            TaggedField::Fallback(_) => 'f',
+            TaggedField::PrivateRoute(_) => 'r',
+            TaggedField::PaymentSecret(_) => 's',
+            TaggedField::PaymentMetadata(_) => 'm',
+            TaggedField::Features(_) => '9',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn software(invoice: &str) -> Option<Software> {
+        let invoice = invoice.parse::<Bolt11Invoice>().unwrap();
+        let details = InvoiceDetails::from(&invoice);
+        fingerprint(&invoice, &details).software
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let invoice = "lnbc1u1pj62kd6pp557unu8u02cg7nqnsj5rnrgsrzctw7f85g9wr6wu3hhwa5qacmhtqdqqcqzzsxqyz5vqsp5arf47cesn7xyjc7wgq7fl288rczl45j4wql5un4tam8jcuchmh2s9qyyssqmzxkcqk9cpau6fu6zv5n5rz9znuuwwevxz073y8f37yv3qrpp3dpwhruf47206q3rv2st2d7jc2v8nxy7pa6ad7s8rsh9zzq5g33t3qq7d5huu";
+        assert_eq!(software(invoice), Some(Software::Lnd));
+
+        let invoice = "lnbc1pjmqfldpp5se7k94vu6h3yj5udwpdmkghxfx3cfp34s8a4jfdl45s4cdr7um5scqpjsp5fhc480wd04cyvw5nas66356fycn6czu7zcjv89u9jyrxzhk44ecq9q7sqqqqqqqqqqqqqqqqqqqsqqqqqysgqdqqmqz9gxqyjw5qrzjqwryaup9lh50kkranzgcdnn2fgvx390wgj5jd07rwr3vxeje0glcllcnpf6kl8z6tsqqqqlgqqqqqeqqjqwaugzchfmhjxu2pwednwyj9fr9v3wn05df3aeq53e5vn72q35huyzek7q8phzm56gnjxgnx2vnmt5tynux0egmhr2uw3zc3jq8h92tqqlcw3tt";
+        assert_eq!(software(invoice), Some(Software::Phoenix));
+
+        let invoice = "lnbc1pjmzpxkdqqnp4q06vyl46x4nucdfqn7n5hhtwml7zkxmk7eyngdsaru6psg3ahnvngpp5f73cm0u7mlmj9lfq2294chfmvesmllujwpj8ky2a6pwtfglc2ffssp509ydmgyap92fegqlg9ppy3c9wzdwysha8r8rst78us6v56xqvp4q9qyysgqcqpcxqrrssrzjq2tt9ke59l8c0655mxqh2l7lf5l9gk74em6fr86ckhfcmlwh806unapyqqqqqqqpkuqqqqqqqqqqqeqq2qp3mkyj40htekxg0g04kzjfs3jh6jtywey3wv7azqrlv9a520jy3pay3dy3cpe0ck24wks2kt5vzqqmhqzavhwemtwu3mc0lekjt4xmsqhd4d22";
+        assert_eq!(software(invoice), Some(Software::Ldk));
+
+        let invoice = "lnbc120n1pjcxr98dp923jhxarfdenjqur9dejxjmn8ypcxz7tdv4h8gpp5p0547ufczxajsnzwylyw082p2mz6cwswmr0z0uyhmgpfn06gc7tqxqrrsssp546n87knlt8hedp9cp30rkgtcduw2hrr00ex62msawwzfqszh0k7s9qrsgqcqzysrzjqfj2v2jrqltsrsz2g6v5ee04xga3eg5vsrrxku7xx8dukzvs6m5r2avk07w5uftf4sqqqqlgqqqqqzsqygs6sp6j4mwstpvjd648cmtndazpnfvhnsh9ff8frgrkmx3jarm0vxyqf822a2d9sefxzyqwlm5epvtcyj5rjpu09lsy4jffu7t0a7xxgqpzsw6v";
+        assert_eq!(software(invoice), Some(Software::BreezSdk));
+
+        let invoice = "lnbc27u1pjmp6jusp55kapartsh8jr2dvg538hnsqcaeaqyaujjj0qmpqx8cqzwxl4y3eqpp55ujktk0pscwe7m4mpwwxv06epxzurmn6d0vlzc3cggjrsmshretqhp5uwcvgs5clswpfxhm7nyfjmaeysn6us0yvjdexn9yjkv3k7zjhp2sxq9z0rgqcqpnrzjqtahnsafzgwctvfxdpaazy02a0ep4242tjljxt3tdsaalzqr7sqcyryy8sqqxssqqyqqqfcsqqqqqzsq9q9qxpqysgqwg7j72jjx873aepqp0qaslwp3887ps2cc0a3zteus3p5zd9fmzxpy5ythgny7t4glt8ar7g2gda2wv9zc4a3jxgv9qynqxw08mnn0ncqp0cyz8";
+        let invoice = invoice.parse::<Bolt11Invoice>().unwrap();
+        let details = InvoiceDetails::from(&invoice);
+        let fingerprint = fingerprint(&invoice, &details);
+        assert_eq!(fingerprint.software, Some(Software::CoreLightning));
+        assert!(fingerprint
+            .signals
+            .contains(&"description hash of an empty string".to_string()));
+    }
+}
@@ -1,47 +1,464 @@
+use crate::channel::{Channel, ChannelPolicy};
+use crate::error::Result;
 use crate::node::Node;
+use crate::offer_details::ShortChannelId;
+use crate::route_hint::estimate_block_height;
 
-use anyhow::Result;
+use bitcoin::secp256k1::PublicKey;
+use bitcoin::Network;
+use chrono::DateTime;
+use lightning::blinded_path::Direction;
+use rusqlite::types::Type;
 use rusqlite::{Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Nodes not announced for this long before the graph snapshot are stale.
+const STALE_AFTER_SECS: i64 = 14 * 24 * 60 * 60;
+
+const POLICY_COLUMNS: &str = "base_fee_msat, fee_proportional_millionths, cltv_expiry_delta,
+    htlc_minimum_msat, htlc_maximum_msat, disabled";
+
+/// Schema of the graph database written by graph-builder.
+pub const GRAPH_SCHEMA: &str = "
+CREATE TABLE nodes (
+    id             INTEGER NOT NULL PRIMARY KEY,
+    pubkey         TEXT NOT NULL,
+    alias          TEXT NOT NULL,
+    capacity       INTEGER NOT NULL,
+    channels       INTEGER NOT NULL,
+    features       TEXT NOT NULL,
+    color          TEXT NOT NULL,
+    last_update    INTEGER NOT NULL,
+    addresses      TEXT NOT NULL,
+    peers          INTEGER NOT NULL,
+    oldest_channel INTEGER,
+    cluster        INTEGER
+);
+
+CREATE TABLE edges (
+    scid       INTEGER NOT NULL PRIMARY KEY,
+    left_node  TEXT NOT NULL,
+    right_node TEXT NOT NULL,
+    capacity   INTEGER NOT NULL
+);
+
+CREATE TABLE policies (
+    scid                        INTEGER NOT NULL,
+    node                        TEXT NOT NULL,
+    base_fee_msat               INTEGER NOT NULL,
+    fee_proportional_millionths INTEGER NOT NULL,
+    cltv_expiry_delta           INTEGER NOT NULL,
+    htlc_minimum_msat           INTEGER NOT NULL,
+    htlc_maximum_msat           INTEGER NOT NULL,
+    disabled                    INTEGER NOT NULL,
+    PRIMARY KEY (scid, node)
+);
+
+CREATE TABLE graph_info (
+    key   TEXT NOT NULL PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
 
 pub struct GraphDatabase {
     connection: Connection,
+    /// Databases built before channels were stored have only nodes.
+    has_edges: bool,
+    /// Databases built before policies were stored have no channel capacities either.
+    has_policies: bool,
+    /// Databases built before node statistics were stored have no peers and channel ages.
+    has_statistics: bool,
+    has_clusters: bool,
+    /// Network of the graph, `None` if the database has no graph.
+    network: Option<Network>,
+    /// Latest node announcement, `None` for databases with only aliases and capacities.
+    snapshot: Option<i64>,
 }
 
 impl GraphDatabase {
-    pub fn open(database_path: &str) -> Result<Self> {
+    pub fn open<P: AsRef<Path>>(database_path: P) -> Result<Self> {
         let connection = Connection::open(database_path)?;
-        Ok(Self { connection })
-    }
-
-    pub fn query(&self, pubkey: String) -> Result<Node> {
-        let node = match self.query_alias(&pubkey)? {
-            Some(alias) if !alias.is_empty() => Node {
-                pubkey,
-                alias: Some(alias),
-                is_announced: true,
-            },
-            Some(_alias) => Node {
-                pubkey,
-                alias: None,
-                is_announced: true,
-            },
-            None => Node {
-                pubkey,
-                alias: None,
-                is_announced: false,
-            },
+        Self::from_connection(connection)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        let connection = Connection::open_in_memory()?;
+        connection.execute_batch(GRAPH_SCHEMA)?;
+        Self::from_connection(connection)
+    }
+
+    pub fn from_connection(connection: Connection) -> Result<Self> {
+        let has_edges = has_table(&connection, "edges")?;
+        let has_policies = has_table(&connection, "policies")?;
+        // Databases built before the network was stored are mainnet graphs.
+        let network = match has_table(&connection, "graph_info")? {
+            true => connection
+                .query_row(
+                    "SELECT value FROM graph_info WHERE key = 'network'",
+                    [],
+                    |row: &Row| row.get::<usize, String>(0),
+                )
+                .optional()?
+                .and_then(|network| Network::from_str(&network).ok()),
+            false => Some(Network::Bitcoin),
+        };
+        let has_announcements = has_column(&connection, "nodes", "last_update")?;
+        let has_statistics = has_column(&connection, "nodes", "oldest_channel")?;
+        let has_clusters = has_column(&connection, "nodes", "cluster")?;
+        let snapshot = match has_announcements {
+            true => Some(connection.query_row(
+                "SELECT COALESCE(MAX(last_update), 0) FROM nodes",
+                [],
+                |row: &Row| row.get::<usize, i64>(0),
+            )?),
+            false => None,
         };
-        Ok(node)
+        Ok(Self {
+            connection,
+            has_edges,
+            has_policies,
+            has_statistics,
+            has_clusters,
+            network,
+            snapshot,
+        })
     }
 
-    fn query_alias(&self, pubkey: &str) -> Result<Option<String>> {
+    /// Whether the graph is of the given network.
+    pub fn has_network(&self, network: Network) -> bool {
+        self.network == Some(network)
+    }
+
+    /// Nodes of other networks than the graph are reported as private.
+    pub fn query(&self, pubkey: String, network: Network) -> Result<Node> {
+        let mut node = match self.has_network(network) {
+            true => self.query_node(&pubkey)?,
+            false => None,
+        };
+        if let Some(node) = node.as_mut().filter(|_| self.has_clusters) {
+            node.operator_nodes = self.query_operator_nodes(&pubkey)?;
+        }
+        Ok(node.unwrap_or(Node {
+            pubkey,
+            ..Default::default()
+        }))
+    }
+
+    fn query_node(&self, pubkey: &str) -> Result<Option<Node>> {
+        let Some(snapshot) = self.snapshot else {
+            return Ok(self
+                .connection
+                .query_row(
+                    "SELECT alias, capacity FROM nodes WHERE pubkey = ?1 LIMIT 1",
+                    [pubkey],
+                    |row: &Row| {
+                        Ok(Node {
+                            pubkey: pubkey.to_string(),
+                            alias: non_empty(row.get(0)?),
+                            is_announced: true,
+                            capacity: row.get(1)?,
+                            ..Default::default()
+                        })
+                    },
+                )
+                .optional()?);
+        };
+        let tip = self
+            .network
+            .and_then(|network| estimate_block_height(snapshot, network));
+        let statistics = match self.has_statistics {
+            true => "peers, oldest_channel",
+            false => "0, NULL",
+        };
         Ok(self
             .connection
             .query_row(
-                "SELECT alias FROM nodes WHERE pubkey = ?1 LIMIT 1",
+                &format!(
+                    "SELECT alias, capacity, channels, features, color, last_update, addresses,
+                        {statistics}
+                     FROM nodes WHERE pubkey = ?1 LIMIT 1"
+                ),
                 [pubkey],
-                |row: &Row| row.get::<usize, String>(0),
+                |row: &Row| {
+                    let features: String = row.get(3)?;
+                    let last_update: i64 = row.get(5)?;
+                    let addresses: String = row.get(6)?;
+                    let oldest_channel: Option<u32> = row.get(8)?;
+                    Ok(Node {
+                        pubkey: pubkey.to_string(),
+                        alias: non_empty(row.get(0)?),
+                        is_announced: true,
+                        capacity: row.get(1)?,
+                        channels: row.get(2)?,
+                        features: features
+                            .split(',')
+                            .filter_map(|bit| bit.parse().ok())
+                            .collect(),
+                        color: non_empty(row.get(4)?),
+                        last_update: DateTime::from_timestamp(last_update, 0),
+                        addresses: split_list(&addresses),
+                        is_stale: snapshot - last_update > STALE_AFTER_SECS,
+                        peers: row.get(7)?,
+                        oldest_channel_age: oldest_channel
+                            .zip(tip)
+                            .map(|(block_height, tip)| tip.saturating_sub(block_height)),
+                        operator_nodes: Vec::new(),
+                    })
+                },
+            )
+            .optional()?)
+    }
+
+    pub fn query_channel_end(
+        &self,
+        scid: u64,
+        direction: Direction,
+        network: Network,
+    ) -> Result<Option<PublicKey>> {
+        if !self.has_edges || !self.has_network(network) {
+            return Ok(None);
+        }
+        let query = match direction {
+            Direction::NodeOne => "SELECT left_node FROM edges WHERE scid = ?1 LIMIT 1",
+            Direction::NodeTwo => "SELECT right_node FROM edges WHERE scid = ?1 LIMIT 1",
+        };
+        Ok(self
+            .connection
+            .query_row(query, [scid as i64], |row: &Row| {
+                let pubkey = row.get::<usize, String>(0)?;
+                PublicKey::from_str(&pubkey).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(0, Type::Text, e.to_string().into())
+                })
+            })
+            .optional()?)
+    }
+
+    fn query_operator_nodes(&self, pubkey: &str) -> Result<Vec<String>> {
+        let mut statement = self.connection.prepare(
+            "SELECT pubkey FROM nodes
+             WHERE cluster = (SELECT cluster FROM nodes WHERE pubkey = ?1) AND pubkey != ?1
+             ORDER BY pubkey",
+        )?;
+        let pubkeys = statement
+            .query_map([pubkey], |row: &Row| row.get::<usize, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(pubkeys)
+    }
+
+    /// Whether the channel is announced, `None` if the database has no channels of the network.
+    pub fn is_public_channel(&self, scid: u64, network: Network) -> Result<Option<bool>> {
+        if !self.has_edges || !self.has_network(network) {
+            return Ok(None);
+        }
+        let found = self
+            .connection
+            .query_row(
+                "SELECT 1 FROM edges WHERE scid = ?1 LIMIT 1",
+                [scid as i64],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(Some(found.is_some()))
+    }
+
+    /// The channel with the policies of both ends, `None` if unknown or not stored.
+    pub fn query_channel(&self, scid: u64, network: Network) -> Result<Option<Channel>> {
+        if !self.has_policies || !self.has_network(network) {
+            return Ok(None);
+        }
+        let channel = self
+            .connection
+            .query_row(
+                "SELECT left_node, right_node, capacity FROM edges WHERE scid = ?1 LIMIT 1",
+                [scid as i64],
+                |row: &Row| {
+                    Ok((
+                        row.get::<usize, String>(0)?,
+                        row.get::<usize, String>(1)?,
+                        row.get::<usize, u64>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((left_node, right_node, capacity)) = channel else {
+            return Ok(None);
+        };
+        Ok(Some(Channel {
+            short_channel_id: ShortChannelId::from(scid),
+            capacity,
+            left_policy: self.query_policy(scid, &left_node)?,
+            right_policy: self.query_policy(scid, &right_node)?,
+            left_node,
+            right_node,
+        }))
+    }
+
+    fn query_policy(&self, scid: u64, pubkey: &str) -> Result<Option<ChannelPolicy>> {
+        Ok(self
+            .connection
+            .query_row(
+                &format!("SELECT {POLICY_COLUMNS} FROM policies WHERE scid = ?1 AND node = ?2"),
+                (scid as i64, pubkey),
+                read_policy,
             )
             .optional()?)
     }
 }
+
+fn read_policy(row: &Row) -> rusqlite::Result<ChannelPolicy> {
+    Ok(ChannelPolicy {
+        base_fee_msat: row.get(0)?,
+        fee_proportional_millionths: row.get(1)?,
+        cltv_expiry_delta: row.get(2)?,
+        htlc_minimum_msat: row.get(3)?,
+        htlc_maximum_msat: row.get(4)?,
+        is_disabled: row.get(5)?,
+    })
+}
+
+fn non_empty(value: String) -> Option<String> {
+    (!value.is_empty()).then_some(value)
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn has_column(connection: &Connection, table: &str, column: &str) -> Result<bool> {
+    Ok(connection
+        .query_row(
+            "SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2",
+            [table, column],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+fn has_table(connection: &Connection, name: &str) -> Result<bool> {
+    Ok(connection
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [name],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_channel_end() {
+        let left = "02c4d6599009cfc6a015562252ad7b14b8a4ed2640aeb69b688c215e3b4ceb5a99";
+        let right = "03864ef025fde8fb587d989186ce6a4a186895ee44a926bfc370e2c366597a3f8f";
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(GRAPH_SCHEMA).unwrap();
+        connection
+            .execute(
+                "INSERT INTO edges(scid, left_node, right_node, capacity) VALUES (?1, ?2, ?3, ?4)",
+                (42i64, left, right, 1_000_000),
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO policies VALUES (42, ?1, 1000, 100, 144, 1000, 990000000, 0)",
+                [left],
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO graph_info(key, value) VALUES ('network', 'bitcoin')",
+                [],
+            )
+            .unwrap();
+        let insert_node = "INSERT INTO nodes(pubkey, alias, capacity, channels, features, color,
+                last_update, addresses, peers, oldest_channel, cluster)
+            VALUES (?1, '', 1000, ?2, '7,13', '#3399ff', ?3, ?4, ?2, 840000, 1)";
+        connection
+            .execute(insert_node, (left, 1, 1_700_000_000, "abc.onion:9735"))
+            .unwrap();
+        connection
+            .execute(insert_node, (right, 2, 1_710_000_000, "1.2.3.4:9735"))
+            .unwrap();
+        let graph_database = GraphDatabase::from_connection(connection).unwrap();
+        let network = Network::Bitcoin;
+
+        let node = graph_database.query_channel_end(42, Direction::NodeOne, network);
+        assert_eq!(node.unwrap().unwrap().to_string(), left);
+        let node = graph_database.query_channel_end(42, Direction::NodeTwo, network);
+        assert_eq!(node.unwrap().unwrap().to_string(), right);
+        let node = graph_database.query_channel_end(43, Direction::NodeOne, network);
+        assert!(node.unwrap().is_none());
+
+        assert_eq!(
+            graph_database.is_public_channel(42, network).unwrap(),
+            Some(true)
+        );
+        assert_eq!(
+            graph_database.is_public_channel(43, network).unwrap(),
+            Some(false)
+        );
+
+        let node = graph_database.query(left.to_string(), network).unwrap();
+        assert_eq!(node.features, vec![7, 13]);
+        assert!(node.is_tor_only());
+        assert!(node.is_stale);
+        let node = graph_database.query(right.to_string(), network).unwrap();
+        assert_eq!(node.channels, 2);
+        assert_eq!(node.peers, 2);
+        assert_eq!(node.operator_nodes, vec![left.to_string()]);
+        assert!(node.oldest_channel_age.is_some());
+        assert!(!node.is_tor_only());
+        assert!(!node.is_stale);
+
+        let channel = graph_database.query_channel(42, network).unwrap().unwrap();
+        assert_eq!(channel.capacity, 1_000_000);
+        let policy = channel.policy_of(left).unwrap();
+        assert_eq!(policy.fee_proportional_millionths, 100);
+        assert!(channel.policy_of(right).is_none());
+
+        let signet = Network::Signet;
+        assert_eq!(graph_database.is_public_channel(42, signet).unwrap(), None);
+        let node = graph_database.query_channel_end(42, Direction::NodeOne, signet);
+        assert!(node.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_without_statistics() {
+        let pubkey = "02c4d6599009cfc6a015562252ad7b14b8a4ed2640aeb69b688c215e3b4ceb5a99";
+        let connection = Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE nodes (
+                    id INTEGER NOT NULL PRIMARY KEY, pubkey TEXT NOT NULL, alias TEXT NOT NULL,
+                    capacity INTEGER NOT NULL, channels INTEGER NOT NULL, features TEXT NOT NULL,
+                    color TEXT NOT NULL, last_update INTEGER NOT NULL, addresses TEXT NOT NULL
+                )",
+            )
+            .unwrap();
+        connection
+            .execute(
+                "INSERT INTO nodes(pubkey, alias, capacity, channels, features, color,
+                    last_update, addresses)
+                VALUES (?1, 'alias', 1000, 3, '7', '#3399ff', 1700000000, 'abc.onion:9735')",
+                [pubkey],
+            )
+            .unwrap();
+        let graph_database = GraphDatabase::from_connection(connection).unwrap();
+
+        let node = graph_database
+            .query(pubkey.to_string(), Network::Bitcoin)
+            .unwrap();
+        assert_eq!(node.channels, 3);
+        assert!(node.is_tor_only());
+        assert_eq!(node.peers, 0);
+        assert_eq!(node.oldest_channel_age, None);
+    }
+}
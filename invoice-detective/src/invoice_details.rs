@@ -0,0 +1,90 @@
+use bech32::{u5, ToBase32};
+use bitcoin::hex::DisplayHex;
+use chrono::{DateTime, Utc};
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescription, Currency, TaggedField};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct InvoiceDetails {
+    pub network: &'static str,
+    pub description: String,
+    pub description_hash: Option<String>,
+    pub amount_msat: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub expiry_time: Duration,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_expired: bool,
+    pub payment_hash: String,
+    pub has_payment_secret: bool,
+    pub min_final_cltv_expiry_delta: u64,
+    pub payment_metadata: Option<String>,
+    pub feature_bits: Vec<usize>,
+    pub fallback_addresses: Vec<String>,
+}
+
+impl From<&Bolt11Invoice> for InvoiceDetails {
+    fn from(invoice: &Bolt11Invoice) -> Self {
+        let network = match invoice.currency() {
+            Currency::Bitcoin => "Mainnet",
+            Currency::BitcoinTestnet => "Testnet",
+            Currency::Regtest => "Regtest",
+            Currency::Simnet => "Simnet",
+            Currency::Signet => "Signet",
+        };
+
+        let (description, description_hash) = match invoice.description() {
+            Bolt11InvoiceDescription::Direct(description) => (description.to_string(), None),
+            Bolt11InvoiceDescription::Hash(hash) => (String::new(), Some(hash.0.to_string())),
+        };
+
+        let created_at = to_date_time(invoice.duration_since_epoch()).unwrap_or_default();
+        let expires_at = invoice.expires_at().and_then(to_date_time);
+        let has_payment_secret = invoice
+            .tagged_fields()
+            .any(|field| matches!(field, TaggedField::PaymentSecret(_)));
+        let payment_metadata = invoice.payment_metadata().map(|m| m.as_hex().to_string());
+        let feature_bits = invoice
+            .features()
+            .map(|features| set_bits(&features.to_base32()))
+            .unwrap_or_default();
+        let fallback_addresses = invoice
+            .fallback_addresses()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+
+        Self {
+            network,
+            description,
+            description_hash,
+            amount_msat: invoice.amount_milli_satoshis(),
+            created_at,
+            expiry_time: invoice.expiry_time(),
+            expires_at,
+            is_expired: invoice.is_expired(),
+            payment_hash: invoice.payment_hash().to_string(),
+            has_payment_secret,
+            min_final_cltv_expiry_delta: invoice.min_final_cltv_expiry_delta(),
+            payment_metadata,
+            feature_bits,
+            fallback_addresses,
+        }
+    }
+}
+
+fn to_date_time(duration: Duration) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(duration.as_secs() as i64, 0)
+}
+
+fn set_bits(base32_flags: &[u5]) -> Vec<usize> {
+    base32_flags
+        .iter()
+        .rev()
+        .enumerate()
+        .flat_map(|(i, word)| {
+            (0..5)
+                .filter(move |bit| word.to_u8() & (1 << bit) != 0)
+                .map(move |bit| i * 5 + bit)
+        })
+        .collect()
+}
@@ -0,0 +1,52 @@
+use crate::chain_hash::ChainHash;
+use crate::offer_details::{Amount, BlindedPath};
+use bitcoin::hex::DisplayHex;
+use chrono::{DateTime, Utc};
+use lightning::offers::invoice_request::InvoiceRequest;
+
+#[derive(Debug)]
+pub struct InvoiceRequestDetails {
+    pub chain: String,
+    pub amount: Option<Amount>,
+    pub offer_amount: Option<Amount>,
+    pub quantity: Option<u64>,
+    pub description: Option<String>,
+    pub issuer: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub signing_pubkey: Option<String>,
+    pub payer_id: String,
+    pub payer_note: Option<String>,
+    pub payer_metadata: String,
+    pub paths: Vec<BlindedPath>,
+}
+
+impl From<InvoiceRequest> for InvoiceRequestDetails {
+    fn from(invoice_request: InvoiceRequest) -> Self {
+        let amount = invoice_request.amount_msats().map(|amount_msats| {
+            Amount::from(lightning::offers::offer::Amount::Bitcoin { amount_msats })
+        });
+        let expires_at = invoice_request
+            .absolute_expiry()
+            .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, 0));
+        let paths = invoice_request
+            .paths()
+            .iter()
+            .map(BlindedPath::from)
+            .collect();
+
+        Self {
+            chain: ChainHash::from(invoice_request.chain()).to_string(),
+            amount,
+            offer_amount: invoice_request.amount().map(Amount::from),
+            quantity: invoice_request.quantity(),
+            description: invoice_request.description().map(|s| s.to_string()),
+            issuer: invoice_request.issuer().map(|s| s.to_string()),
+            expires_at,
+            signing_pubkey: invoice_request.signing_pubkey().map(|k| k.to_string()),
+            payer_id: invoice_request.payer_id().to_string(),
+            payer_note: invoice_request.payer_note().map(|s| s.to_string()),
+            payer_metadata: invoice_request.payer_metadata().as_hex().to_string(),
+            paths,
+        }
+    }
+}
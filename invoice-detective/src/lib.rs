@@ -1,52 +1,87 @@
+pub mod bolt12_invoice_details;
+mod builder;
 mod chain_hash;
+mod channel;
+mod confidence;
 pub mod decoder;
+mod error;
+mod evidence;
+mod fingerprint;
 mod graph_database;
+mod invoice_details;
+pub mod invoice_request_details;
 mod node;
 pub mod offer_details;
+mod provider_registry;
 mod recipient;
+pub mod refund_details;
+mod route_hint;
 
+pub use crate::builder::{GraphSource, InvoiceDetectiveBuilder};
+pub use crate::channel::{Channel, ChannelPolicy};
+pub use crate::confidence::{Confidence, ConfidenceLevel};
+pub use crate::error::{Error, Result};
+pub use crate::evidence::Evidence;
+pub use crate::fingerprint::{Fingerprint, Software};
 use crate::graph_database::GraphDatabase;
+pub use crate::graph_database::GRAPH_SCHEMA;
+pub use crate::invoice_details::InvoiceDetails;
 pub use crate::node::Node;
+use crate::offer_details::ShortChannelId;
+pub use crate::provider_registry::ProviderRegistry;
 use crate::recipient::RecipientDecoder;
-pub use crate::recipient::{RecipientNode, ServiceKind};
-use anyhow::{anyhow, Result};
+pub use crate::recipient::{Attribution, CustodyModel, Provider, RecipientNode, ServiceKind};
+pub use crate::route_hint::{ChannelKind, Hop};
+use bitcoin::constants::ChainHash;
 use bitcoin::secp256k1::PublicKey;
+use bitcoin::Network;
+use chrono::{DateTime, NaiveDate};
 use lightning::blinded_path::message::BlindedMessagePath;
+use lightning::blinded_path::payment::BlindedPaymentPath;
 use lightning::blinded_path::IntroductionNode;
+use lightning::offers::invoice::Bolt12Invoice;
+use lightning::offers::invoice_request::InvoiceRequest;
 use lightning::offers::offer::Offer;
+use lightning::offers::refund::Refund;
 use lightning_invoice::{Bolt11Invoice, Currency, RouteHint};
-
-#[derive(Debug, Default)]
-pub struct InvoiceDetails {
-    pub network: &'static str,
-    pub description: String,
-    pub amount_msat: Option<u64>,
-    // pub date: u64,
-    // pub expires_at: u64,
-}
+pub use rusqlite::Connection;
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub struct InvestigativeFindings {
     pub recipient: RecipientNode,
+    pub confidence: Confidence,
+    /// Software which generated the invoice, `None` for BOLT12.
+    pub fingerprint: Option<Fingerprint>,
+    pub evidences: Vec<Evidence>,
     pub payee: Node,
-    pub route_hints: Vec<Vec<Node>>,
-    pub details: InvoiceDetails,
+    pub route_hints: Vec<Vec<Hop>>,
+    pub blinded_paths: Vec<BlindedPathFindings>,
+    pub details: Option<InvoiceDetails>,
+}
+
+#[derive(Debug)]
+pub struct BlindedPathFindings {
+    /// `None` if the introduction node channel is not found in the graph.
+    pub introduction_node: Option<Node>,
+    pub recipient: RecipientNode,
+    pub confidence: Confidence,
 }
 
 pub struct InvoiceDetective {
-    graph_database: GraphDatabase,
-    recipient_decoder: RecipientDecoder,
+    /// The graph of the builder's `graph_source` goes first.
+    graph_databases: Vec<GraphDatabase>,
+    /// Decoders matching against the providers of each network.
+    recipient_decoders: HashMap<Network, RecipientDecoder>,
 }
 
 impl InvoiceDetective {
     pub fn new() -> Result<Self> {
-        const DATABASE_PATH: &str = "./graph.db3";
-        let graph_database = GraphDatabase::open(DATABASE_PATH)?;
-        let recipient_decoder = RecipientDecoder::new();
-        Ok(Self {
-            graph_database,
-            recipient_decoder,
-        })
+        Self::builder().build()
+    }
+
+    pub fn builder() -> InvoiceDetectiveBuilder {
+        InvoiceDetectiveBuilder::default()
     }
 
     pub fn investigate(&self, invoice: &str) -> Result<InvestigativeFindings> {
@@ -55,96 +90,273 @@ impl InvoiceDetective {
     }
 
     pub fn investigate_bolt11(&self, invoice: Bolt11Invoice) -> Result<InvestigativeFindings> {
-        let description = invoice.description().to_string();
         let pubkey = invoice
             .payee_pub_key()
             .copied()
             .unwrap_or_else(|| invoice.recover_payee_pub_key())
             .to_string();
-        let payee = self.graph_database.query(pubkey.clone())?;
-        let route_hints = self.process_route_hints(&invoice.route_hints())?;
-        let recipient = self.recipient_decoder.decode(&pubkey, &route_hints);
-
-        let network = match invoice.currency() {
-            Currency::Bitcoin => "Mainnet",
-            Currency::BitcoinTestnet => "Testnet",
-            Currency::Regtest => "Regtest",
-            Currency::Simnet => "Simnet",
-            Currency::Signet => "Signet",
-        };
-
-        let details = InvoiceDetails {
-            network,
-            description,
-            amount_msat: invoice.amount_milli_satoshis(),
-        };
+        let network = network_of_currency(invoice.currency());
+        let payee = self.graph_database(network).query(pubkey, network)?;
+        let created_at = invoice.duration_since_epoch().as_secs() as i64;
+        let route_hints = self.process_route_hints(&invoice.route_hints(), created_at, network)?;
+        let hint_nodes = route_hints
+            .iter()
+            .map(|hint| hint.iter().map(|hop| hop.node.clone()).collect())
+            .collect::<Vec<_>>();
+        let date = date_of(created_at);
+        let mut evidences = self.graph_evidences(network);
+        let recipient_decoder = self.recipient_decoder(network)?;
+        let recipient = recipient_decoder
+            .decode_phantom(&payee, &route_hints, date, &mut evidences)
+            .or_else(|| {
+                recipient_decoder.decode_ecash_gateway(&payee, &route_hints, date, &mut evidences)
+            })
+            .unwrap_or_else(|| recipient_decoder.decode(&payee, &hint_nodes, date, &mut evidences));
+        let details = InvoiceDetails::from(&invoice);
+        let recipient = recipient::decode_unknown_swap(recipient, &details);
+        recipient::payee_traits(&payee, &mut evidences);
+        let confidence = confidence::assess(&recipient, &payee, &hint_nodes, date);
+        if let RecipientNode::Swap { .. } | RecipientNode::UnknownSwap { .. } = recipient {
+            recipient::swap_traits(&details, &mut evidences);
+        }
+        let fingerprint = fingerprint::fingerprint(&invoice, &details);
 
         Ok(InvestigativeFindings {
             recipient,
+            confidence,
+            fingerprint: Some(fingerprint),
+            evidences,
             payee,
             route_hints,
-            details,
+            blinded_paths: Vec::new(),
+            details: Some(details),
         })
     }
 
     pub fn investigate_bolt12(&self, offer: Offer) -> Result<InvestigativeFindings> {
-        let introduction_node = offer
+        let paths = offer
             .paths()
+            .iter()
+            .map(BlindedMessagePath::introduction_node);
+        let network = offer
+            .chains()
             .first()
+            .map_or(Network::Bitcoin, |chain| network_of_chain(*chain));
+        self.investigate_blinded_paths(offer.signing_pubkey(), paths.collect(), None, network)
+    }
+
+    pub fn investigate_refund(&self, refund: Refund) -> Result<InvestigativeFindings> {
+        let paths = refund
+            .paths()
+            .iter()
             .map(BlindedMessagePath::introduction_node);
-        let destination = match introduction_node {
-            Some(IntroductionNode::NodeId(introduction_node_id)) => Destination::Blinded {
-                introduction_node_id: *introduction_node_id,
+        let network = network_of_chain(refund.chain());
+        self.investigate_blinded_paths(Some(refund.payer_id()), paths.collect(), None, network)
+    }
+
+    pub fn investigate_invoice_request(
+        &self,
+        invoice_request: InvoiceRequest,
+    ) -> Result<InvestigativeFindings> {
+        let paths = invoice_request
+            .paths()
+            .iter()
+            .map(BlindedMessagePath::introduction_node);
+        let network = network_of_chain(invoice_request.chain());
+        self.investigate_blinded_paths(
+            invoice_request.signing_pubkey(),
+            paths.collect(),
+            None,
+            network,
+        )
+    }
+
+    pub fn investigate_bolt12_invoice(
+        &self,
+        invoice: Bolt12Invoice,
+    ) -> Result<InvestigativeFindings> {
+        let paths = invoice
+            .payment_paths()
+            .iter()
+            .map(BlindedPaymentPath::introduction_node);
+        let created_at = date_of(invoice.created_at().as_secs() as i64);
+        self.investigate_blinded_paths(
+            Some(invoice.signing_pubkey()),
+            paths.collect(),
+            created_at,
+            network_of_chain(invoice.chain()),
+        )
+    }
+
+    fn investigate_blinded_paths(
+        &self,
+        signing_pubkey: Option<PublicKey>,
+        paths: Vec<&IntroductionNode>,
+        created_at: Option<NaiveDate>,
+        network: Network,
+    ) -> Result<InvestigativeFindings> {
+        let graph_database = self.graph_database(network);
+        let mut introduction_nodes = Vec::new();
+        for path in &paths {
+            let introduction_node = match path {
+                IntroductionNode::NodeId(pubkey) => Some(*pubkey),
+                IntroductionNode::DirectedShortChannelId(direction, scid) => {
+                    graph_database.query_channel_end(*scid, *direction, network)?
+                }
+            };
+            let introduction_node = introduction_node
+                .map(|pubkey| graph_database.query(pubkey.to_string(), network))
+                .transpose()?;
+            introduction_nodes.push(introduction_node);
+        }
+
+        let payee = match signing_pubkey {
+            Some(signing_pubkey) => graph_database.query(signing_pubkey.to_string(), network)?,
+            None => match introduction_nodes.iter().flatten().next() {
+                Some(introduction_node) => introduction_node.clone(),
+                None => return Err(unresolved_destination_error(&paths)),
             },
-            Some(IntroductionNode::DirectedShortChannelId(_direction, _channel_id)) => {
-                unimplemented!();
-            }
-            None => Destination::Node(
-                offer
-                    .signing_pubkey()
-                    .ok_or(anyhow!("Blinded path and signing key are empty"))?,
-            ),
         };
-        let pubkey = destination.pubkey().to_string();
-        let payee = self.graph_database.query(pubkey.clone())?;
-        let recipient = self.recipient_decoder.decode(&pubkey, &Vec::new());
 
-        let details = InvoiceDetails::default();
+        // Without a signing key the recipient node id is hidden behind the blinded paths.
+        let id = signing_pubkey
+            .map(|pubkey| pubkey.to_string())
+            .unwrap_or_default();
+        let recipient_decoder = self.recipient_decoder(network)?;
+        let mut evidences = self.graph_evidences(network);
+        let mut blinded_paths = Vec::new();
+        for introduction_node in introduction_nodes {
+            let (recipient, confidence) = match &introduction_node {
+                Some(node) => {
+                    let recipient = recipient_decoder.decode_blinded_path(
+                        &id,
+                        node,
+                        created_at,
+                        &mut evidences,
+                    );
+                    let hints = [vec![node.clone()]];
+                    let confidence = confidence::assess(&recipient, &payee, &hints, created_at);
+                    (recipient, confidence)
+                }
+                None => {
+                    evidences.push(Evidence::IntroductionNodeUnresolved);
+                    let recipient = RecipientNode::Unknown;
+                    let confidence = confidence::assess(&recipient, &payee, &[], created_at);
+                    (recipient, confidence)
+                }
+            };
+            blinded_paths.push(BlindedPathFindings {
+                introduction_node,
+                recipient,
+                confidence,
+            });
+        }
+
+        let recipient = if blinded_paths.is_empty() {
+            recipient_decoder.decode(&payee, &[], created_at, &mut evidences)
+        } else {
+            RecipientNode::combine(blinded_paths.iter().map(|p| p.recipient.clone()).collect())
+        };
+        recipient::payee_traits(&payee, &mut evidences);
+        let hints = blinded_paths
+            .iter()
+            .filter_map(|path| path.introduction_node.clone())
+            .map(|node| vec![node])
+            .collect::<Vec<_>>();
+        let confidence = confidence::assess(&recipient, &payee, &hints, created_at);
+
         Ok(InvestigativeFindings {
             recipient,
+            confidence,
+            fingerprint: None,
+            evidences,
             payee,
             route_hints: Vec::new(),
-            details,
+            blinded_paths,
+            details: None,
         })
     }
 
-    fn process_route_hints(&self, route_hints: &Vec<RouteHint>) -> Result<Vec<Vec<Node>>> {
+    fn process_route_hints(
+        &self,
+        route_hints: &Vec<RouteHint>,
+        created_at: i64,
+        network: Network,
+    ) -> Result<Vec<Vec<Hop>>> {
+        let graph_database = self.graph_database(network);
         let mut result = Vec::new();
         for hint in route_hints {
             let mut x = Vec::new();
             for hop in &hint.0 {
-                let node = self.graph_database.query(hop.src_node_id.to_string())?;
-                x.push(node);
+                let node = graph_database.query(hop.src_node_id.to_string(), network)?;
+                let is_public = graph_database.is_public_channel(hop.short_channel_id, network)?;
+                let public_policy = graph_database
+                    .query_channel(hop.short_channel_id, network)?
+                    .and_then(|channel| channel.policy_of(&node.pubkey).cloned());
+                x.push(Hop::new(
+                    node,
+                    hop,
+                    is_public,
+                    public_policy,
+                    created_at,
+                    network,
+                ));
             }
             result.push(x);
         }
         Ok(result)
     }
+
+    /// The graph of the network, or the first graph if none is of the network.
+    fn graph_database(&self, network: Network) -> &GraphDatabase {
+        self.graph_databases
+            .iter()
+            .find(|graph_database| graph_database.has_network(network))
+            .unwrap_or(&self.graph_databases[0])
+    }
+
+    fn recipient_decoder(&self, network: Network) -> Result<&RecipientDecoder> {
+        self.recipient_decoders
+            .get(&network)
+            .ok_or_else(|| Error::Unsupported(format!("Network {network} is not supported")))
+    }
+
+    fn graph_evidences(&self, network: Network) -> Vec<Evidence> {
+        match self.graph_database(network).has_network(network) {
+            true => Vec::new(),
+            false => vec![Evidence::GraphMissingNetwork { network }],
+        }
+    }
 }
 
-#[derive(Debug)]
-enum Destination {
-    Node(PublicKey),
-    Blinded { introduction_node_id: PublicKey },
+/// Chains unknown to rust-bitcoin are local test networks.
+fn network_of_chain(chain: ChainHash) -> Network {
+    Network::from_chain_hash(chain).unwrap_or(Network::Regtest)
 }
 
-impl Destination {
-    fn pubkey(&self) -> &PublicKey {
-        match self {
-            Destination::Node(key) => key,
-            Destination::Blinded {
-                introduction_node_id,
-            } => introduction_node_id,
-        }
+fn network_of_currency(currency: Currency) -> Network {
+    match currency {
+        Currency::Bitcoin => Network::Bitcoin,
+        Currency::BitcoinTestnet => Network::Testnet,
+        Currency::Signet => Network::Signet,
+        Currency::Regtest | Currency::Simnet => Network::Regtest,
+    }
+}
+
+/// Date of a unix timestamp, to match against validity windows of attributions.
+fn date_of(timestamp: i64) -> Option<NaiveDate> {
+    DateTime::from_timestamp(timestamp, 0).map(|datetime| datetime.date_naive())
+}
+
+fn unresolved_destination_error(introduction_nodes: &[&IntroductionNode]) -> Error {
+    let scid = introduction_nodes
+        .iter()
+        .find_map(|introduction_node| match introduction_node {
+            IntroductionNode::DirectedShortChannelId(_, scid) => Some(*scid),
+            IntroductionNode::NodeId(_) => None,
+        });
+    match scid {
+        Some(scid) => Error::UnknownChannel(ShortChannelId::from(scid).to_string()),
+        None => Error::Parse("Blinded path and signing key are empty".to_string()),
     }
 }
@@ -1,8 +1,46 @@
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 
-#[derive(Debug, Serialize)]
+/// Nodes with at least this many channels are major routing nodes.
+const MAJOR_ROUTING_NODE_CHANNELS: u32 = 100;
+
+#[derive(PartialEq, Clone, Debug, Default, Serialize)]
 pub struct Node {
     pub pubkey: String,
     pub alias: Option<String>,
     pub is_announced: bool,
+    /// Total capacity of announced channels in sats.
+    pub capacity: u64,
+    /// Number of announced channels.
+    pub channels: u32,
+    /// Number of distinct channel peers.
+    pub peers: u32,
+    /// Blocks between the funding of the oldest channel and the graph snapshot.
+    pub oldest_channel_age: Option<u32>,
+    /// Feature bits of the node announcement.
+    pub features: Vec<usize>,
+    /// Color of the node announcement as `#rrggbb`.
+    pub color: Option<String>,
+    pub last_update: Option<DateTime<Utc>>,
+    /// Advertised addresses as `host:port`.
+    pub addresses: Vec<String>,
+    /// The announcement was not refreshed for two weeks before the graph snapshot.
+    pub is_stale: bool,
+    /// Other nodes probably run by the same operator.
+    pub operator_nodes: Vec<String>,
+}
+
+impl Node {
+    /// Whether the node is reachable only through Tor.
+    pub fn is_tor_only(&self) -> bool {
+        !self.addresses.is_empty()
+            && self
+                .addresses
+                .iter()
+                .all(|address| address.contains(".onion"))
+    }
+
+    pub fn is_major_routing_node(&self) -> bool {
+        self.channels >= MAJOR_ROUTING_NODE_CHANNELS
+    }
 }
@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use core::str;
 use lightning::offers::offer::{Offer, Quantity};
 use lightning::util::scid_utils::{block_from_scid, tx_index_from_scid, vout_from_scid};
+use serde::Serialize;
 use std::fmt::Display;
 
 #[derive(Debug)]
@@ -95,7 +96,7 @@ impl From<&lightning::blinded_path::BlindedHop> for BlindedHop {
     }
 }
 
-#[derive(Debug)]
+#[derive(PartialEq, Clone, Copy, Debug, Serialize)]
 pub struct ShortChannelId {
     pub block_height: u32,
     pub transaction_index: u32,
@@ -162,6 +163,16 @@ impl From<&lightning::blinded_path::message::BlindedMessagePath> for BlindedPath
     }
 }
 
+impl From<&lightning::blinded_path::payment::BlindedPaymentPath> for BlindedPath {
+    fn from(path: &lightning::blinded_path::payment::BlindedPaymentPath) -> Self {
+        Self {
+            introduction_node: path.introduction_node().into(),
+            blinding_point: path.blinding_point().to_string(),
+            hops: path.blinded_hops().iter().map(BlindedHop::from).collect(),
+        }
+    }
+}
+
 impl From<Offer> for OfferDetails {
     fn from(offer: Offer) -> Self {
         let mut chains = offer
@@ -0,0 +1,242 @@
+use crate::error::{Error, Result};
+use crate::recipient::{Attribution, CustodyModel, Provider, ServiceKind};
+use bitcoin::Network;
+use serde::Deserialize;
+use std::path::Path;
+
+const DEFAULT_PROVIDERS: &str = include_str!("../providers.toml");
+const SUPPORTED_VERSION: u32 = 1;
+
+/// Known service providers the recipient of a payment is matched against.
+#[derive(Clone, Debug)]
+pub struct ProviderRegistry {
+    /// Providers holding funds on behalf of their users.
+    pub custodians: Vec<Provider>,
+    /// LSPs wrapping the invoice of a non-custodial recipient.
+    pub wrapping_lsps: Vec<Provider>,
+    /// LSPs appearing in route hints of non-custodial recipients.
+    pub lsps: Vec<Provider>,
+    /// Services swapping lightning payments to on-chain or Liquid funds.
+    pub swap_services: Vec<Provider>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ProviderRole {
+    Custodian,
+    WrappingLsp,
+    Lsp,
+    SwapService,
+}
+
+#[derive(Deserialize)]
+struct ProviderEntry {
+    name: String,
+    service: ServiceKind,
+    role: ProviderRole,
+    node_ids: Vec<String>,
+    website: Option<String>,
+    #[serde(default)]
+    domains: Vec<String>,
+    jurisdiction: Option<String>,
+    #[serde(default)]
+    custody: CustodyModel,
+    #[serde(default)]
+    attributions: Vec<Attribution>,
+    network: Option<Network>,
+}
+
+#[derive(Deserialize)]
+struct ProviderFile {
+    version: u32,
+    #[serde(default)]
+    providers: Vec<ProviderEntry>,
+}
+
+impl ProviderRegistry {
+    /// A registry without any providers.
+    pub fn empty() -> Self {
+        Self {
+            custodians: Vec::new(),
+            wrapping_lsps: Vec::new(),
+            lsps: Vec::new(),
+            swap_services: Vec::new(),
+        }
+    }
+
+    /// Parses a registry in the format of `providers.toml`.
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        let file: ProviderFile =
+            toml::from_str(toml).map_err(|e| Error::ProviderRegistry(e.to_string()))?;
+        if file.version != SUPPORTED_VERSION {
+            return Err(Error::ProviderRegistry(format!(
+                "Unsupported version {}, expected {SUPPORTED_VERSION}",
+                file.version
+            )));
+        }
+
+        let mut registry = Self::empty();
+        for entry in file.providers {
+            if let Some(attribution) = entry
+                .attributions
+                .iter()
+                .find(|attribution| !entry.node_ids.contains(&attribution.node_id))
+            {
+                return Err(Error::ProviderRegistry(format!(
+                    "Attributed node {} is not a node of {}",
+                    attribution.node_id, entry.name
+                )));
+            }
+            if let Some(attribution) = entry.attributions.iter().find(|attribution| {
+                matches!(
+                    (attribution.active_from, attribution.active_until),
+                    (Some(from), Some(until)) if from > until
+                )
+            }) {
+                return Err(Error::ProviderRegistry(format!(
+                    "Attribution of node {} of {} ends before it starts",
+                    attribution.node_id, entry.name
+                )));
+            }
+            let provider = Provider {
+                service: entry.service,
+                name: entry.name,
+                node_ids: entry.node_ids,
+                website: entry.website,
+                domains: entry.domains,
+                jurisdiction: entry.jurisdiction,
+                custody: entry.custody,
+                attributions: entry.attributions,
+                network: entry.network.unwrap_or(Network::Bitcoin),
+            };
+            match entry.role {
+                ProviderRole::Custodian => registry.custodians.push(provider),
+                ProviderRole::WrappingLsp => registry.wrapping_lsps.push(provider),
+                ProviderRole::Lsp => registry.lsps.push(provider),
+                ProviderRole::SwapService => registry.swap_services.push(provider),
+            }
+        }
+        Ok(registry)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let toml = std::fs::read_to_string(path)
+            .map_err(|e| Error::ProviderRegistry(format!("{}: {e}", path.display())))?;
+        Self::from_toml(&toml)
+    }
+
+    /// Providers with node ids on the given network.
+    pub fn for_network(&self, network: Network) -> Self {
+        let filter = |providers: &Vec<Provider>| {
+            providers
+                .iter()
+                .filter(|provider| provider.network == network)
+                .cloned()
+                .collect()
+        };
+        Self {
+            custodians: filter(&self.custodians),
+            wrapping_lsps: filter(&self.wrapping_lsps),
+            lsps: filter(&self.lsps),
+            swap_services: filter(&self.swap_services),
+        }
+    }
+
+    /// Merges `other` on top of this registry.
+    ///
+    /// A provider replaces the one with the same name, role and network, others are appended.
+    pub fn merge(&mut self, other: ProviderRegistry) {
+        merge_providers(&mut self.custodians, other.custodians);
+        merge_providers(&mut self.wrapping_lsps, other.wrapping_lsps);
+        merge_providers(&mut self.lsps, other.lsps);
+        merge_providers(&mut self.swap_services, other.swap_services);
+    }
+
+    /// Merges a user-supplied override file on top of this registry.
+    pub fn merge_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        self.merge(Self::from_file(path)?);
+        Ok(self)
+    }
+}
+
+fn merge_providers(providers: &mut Vec<Provider>, overrides: Vec<Provider>) {
+    for provider in overrides {
+        match providers
+            .iter_mut()
+            .find(|p| p.name == provider.name && p.network == provider.network)
+        {
+            Some(existing) => *existing = provider,
+            None => providers.push(provider),
+        }
+    }
+}
+
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::from_toml(DEFAULT_PROVIDERS).expect("Embedded provider registry is invalid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_merge() {
+        let mut registry = ProviderRegistry::default();
+        let lsps = registry.lsps.len();
+        let overrides = r#"
+            version = 1
+
+            [[providers]]
+            name = "Phoenix"
+            service = "ConsumerWallet"
+            role = "lsp"
+            node_ids = ["02aaaa"]
+
+            [[providers]]
+            name = "New LSP"
+            service = "Lsp"
+            role = "lsp"
+            node_ids = ["02bbbb"]
+            website = "https://lsp.example"
+            custody = "NonCustodial"
+
+            [[providers.attributions]]
+            node_id = "02bbbb"
+            source = "https://lsp.example/nodes"
+            date = "2024-05-01"
+        "#;
+        registry.merge(ProviderRegistry::from_toml(overrides).unwrap());
+
+        assert_eq!(registry.lsps.len(), lsps + 1);
+        let phoenix = registry.lsps.iter().find(|p| p.name == "Phoenix").unwrap();
+        assert_eq!(phoenix.node_ids, vec!["02aaaa"]);
+        let new_lsp = registry.lsps.iter().find(|p| p.name == "New LSP").unwrap();
+        assert_eq!(new_lsp.custody, CustodyModel::NonCustodial);
+        let attribution = new_lsp.attribution("02bbbb").unwrap();
+        assert_eq!(attribution.date, NaiveDate::from_ymd_opt(2024, 5, 1));
+
+        let overrides = r#"
+            version = 1
+
+            [[providers]]
+            name = "Phoenix"
+            service = "ConsumerWallet"
+            role = "lsp"
+            network = "signet"
+            node_ids = ["02cccc"]
+        "#;
+        registry.merge(ProviderRegistry::from_toml(overrides).unwrap());
+        let signet = registry.for_network(Network::Signet);
+        assert_eq!(signet.lsps.len(), 1);
+        assert_eq!(signet.lsps[0].node_ids, vec!["02cccc"]);
+        let mainnet = registry.for_network(Network::Bitcoin);
+        assert_eq!(mainnet.lsps.len(), lsps + 1);
+
+        let result = ProviderRegistry::from_toml("version = 2");
+        assert!(matches!(result, Err(Error::ProviderRegistry(_))));
+    }
+}
@@ -1,12 +1,66 @@
+use crate::evidence::Evidence;
+use crate::invoice_details::InvoiceDetails;
 use crate::node::Node;
-use serde::Serialize;
+use crate::provider_registry::ProviderRegistry;
+use crate::route_hint::Hop;
+use bitcoin::Network;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Clone, Debug, Serialize)]
+/// Min final CLTV expiry delta long enough for an on-chain lockup of a swap.
+const SWAP_MIN_FINAL_CLTV_EXPIRY_DELTA: u64 = 144;
+
+/// Min final CLTV expiry delta of Breez SDK invoices, which are not swaps.
+const BREEZ_SDK_MIN_FINAL_CLTV_EXPIRY_DELTA: u64 = 144;
+
+/// Route hint policy of Fedimint gateways with default fees, see `fedimint-ln-client`.
+const FEDIMINT_BASE_FEE_MSAT: u32 = 0;
+const FEDIMINT_FEE_PROPORTIONAL_MILLIONTHS: u32 = 10_000;
+const FEDIMINT_CLTV_EXPIRY_DELTA: u16 = 30;
+
+/// `MIN_CLTV_EXPIRY_DELTA` of LDK used for the last hop of phantom route hints.
+const LDK_MIN_CLTV_EXPIRY_DELTA: u16 = 6 * 7;
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum ServiceKind {
     BusinessWallet,
     ConsumerWallet,
     Exchange,
     Lsp,
+    SwapService,
+    EcashMint,
+}
+
+/// Who holds the funds of the users, independent of the kind of service.
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum CustodyModel {
+    Custodial,
+    NonCustodial,
+    #[default]
+    Unknown,
+}
+
+/// Where the attribution of a node id to a provider comes from.
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Attribution {
+    pub node_id: String,
+    pub source: String,
+    pub date: Option<NaiveDate>,
+    /// First day the provider operated the node.
+    pub active_from: Option<NaiveDate>,
+    /// Last day the provider operated the node, set for retired nodes.
+    pub active_until: Option<NaiveDate>,
+}
+
+impl Attribution {
+    pub fn is_active(&self, date: NaiveDate) -> bool {
+        self.active_from.is_none_or(|from| from <= date)
+            && self.active_until.is_none_or(|until| date <= until)
+    }
+
+    pub fn is_retired(&self, date: NaiveDate) -> bool {
+        self.active_until.is_some_and(|until| until < date)
+    }
 }
 
 #[derive(PartialEq, Clone, Debug, Serialize)]
@@ -14,6 +68,15 @@ pub struct Provider {
     pub service: ServiceKind,
     pub name: String,
     pub node_ids: Vec<String>,
+    pub website: Option<String>,
+    /// Domains of Lightning addresses and LNURL endpoints.
+    pub domains: Vec<String>,
+    /// ISO 3166-1 alpha-2 code of the country of incorporation.
+    pub jurisdiction: Option<String>,
+    pub custody: CustodyModel,
+    pub attributions: Vec<Attribution>,
+    /// Network the node ids are on.
+    pub network: Network,
 }
 
 impl Provider {
@@ -22,293 +85,886 @@ impl Provider {
             service,
             name: name.to_string(),
             node_ids: ids.into_iter().map(String::from).collect(),
+            website: None,
+            domains: Vec::new(),
+            jurisdiction: None,
+            custody: CustodyModel::Unknown,
+            attributions: Vec::new(),
+            network: Network::Bitcoin,
         }
     }
+
+    pub fn attribution(&self, node_id: &str) -> Option<&Attribution> {
+        self.attributions
+            .iter()
+            .find(|attribution| attribution.node_id == node_id)
+    }
+
+    /// Whether the provider operated the node at the date, always for nodes without attributions.
+    pub fn operated(&self, node_id: &str, date: NaiveDate) -> bool {
+        let mut attributions = self
+            .attributions
+            .iter()
+            .filter(|attribution| attribution.node_id == node_id)
+            .peekable();
+        attributions.peek().is_none() || attributions.any(|attribution| attribution.is_active(date))
+    }
 }
 
-#[derive(PartialEq, Debug, Serialize)]
+#[derive(PartialEq, Clone, Debug, Serialize)]
 pub enum RecipientNode {
-    Custodial { custodian: Provider },
-    NonCustodial { id: String, lsp: Provider },
-    NonCustodialWrapped { lsp: Provider },
+    Custodial {
+        custodian: Provider,
+    },
+    NonCustodial {
+        id: String,
+        lsp: Provider,
+    },
+    NonCustodialWrapped {
+        lsp: Provider,
+    },
+    /// The payment will be swapped to on-chain or Liquid funds.
+    Swap {
+        swap_service: Provider,
+    },
+    /// The payee is unknown, but the invoice has traits of a swap.
+    UnknownSwap {
+        min_final_cltv_expiry_delta: u64,
+    },
+    /// A node with announced channels which has the LSP as a peer.
+    PublicRoutingNode {
+        id: String,
+        alias: Option<String>,
+        lsp: Provider,
+    },
+    /// Invoice of an LDK phantom node served by several real nodes.
+    PhantomNode {
+        id: String,
+        real_nodes: Vec<Node>,
+        provider: Option<Provider>,
+    },
+    /// Custodial ecash mint reached through an unknown Fedimint gateway.
+    EcashGateway {
+        id: String,
+        pubkey: String,
+        alias: Option<String>,
+        /// Index of the federation in the gateway, encoded as the SCID of the route hint.
+        federation_index: u64,
+    },
+    /// All route hints, possibly a single one, enter through the same node which is not a known
+    /// provider.
+    SharedEntryNode {
+        id: String,
+        pubkey: String,
+        alias: Option<String>,
+    },
+    Ambiguous {
+        candidates: Vec<RecipientNode>,
+    },
     Unknown,
 }
 
+impl RecipientNode {
+    /// The provider the recipient is attributed to, `None` if unknown or ambiguous.
+    pub fn provider(&self) -> Option<&Provider> {
+        match self {
+            RecipientNode::Custodial { custodian } => Some(custodian),
+            RecipientNode::NonCustodial { lsp, .. } => Some(lsp),
+            RecipientNode::NonCustodialWrapped { lsp } => Some(lsp),
+            RecipientNode::Swap { swap_service } => Some(swap_service),
+            RecipientNode::PhantomNode { provider, .. } => provider.as_ref(),
+            RecipientNode::PublicRoutingNode { .. }
+            | RecipientNode::UnknownSwap { .. }
+            | RecipientNode::EcashGateway { .. }
+            | RecipientNode::SharedEntryNode { .. }
+            | RecipientNode::Ambiguous { .. }
+            | RecipientNode::Unknown => None,
+        }
+    }
+
+    pub(crate) fn combine(recipients: Vec<RecipientNode>) -> RecipientNode {
+        let mut candidates = Vec::new();
+        for recipient in recipients {
+            if recipient != RecipientNode::Unknown && !candidates.contains(&recipient) {
+                candidates.push(recipient);
+            }
+        }
+        match candidates.len() {
+            0 => RecipientNode::Unknown,
+            1 => candidates.remove(0),
+            _ => RecipientNode::Ambiguous { candidates },
+        }
+    }
+}
+
 pub(crate) struct RecipientDecoder {
     custodians: Vec<Provider>,
     wrapping_lsp: Vec<Provider>,
     lsps: Vec<Provider>,
+    swap_services: Vec<Provider>,
 }
 
 impl RecipientDecoder {
-    pub fn new() -> RecipientDecoder {
-        let voltage = Provider::new(
-            ServiceKind::Lsp,
-            "Voltage Flow 2.0",
-            vec!["03aefa43fbb4009b21a4129d05953974b7dbabbbfb511921410080860fca8ee1f0"],
-        );
-
-        let custodians = vec![
-            Provider::new(
-                ServiceKind::Exchange,
-                "Kraken",
-                vec!["02f1a8c87607f415c8f22c00593002775941dea48869ce23096af27b0cfdcc0b69"],
-            ),
-            Provider::new(
-                ServiceKind::Exchange,
-                "Bitstamp",
-                vec!["02a04446caa81636d60d63b066f2814cbd3a6b5c258e3172cbdded7a16e2cfff4c"],
-            ),
-            Provider::new(
-                ServiceKind::Exchange,
-                "Okcoin",
-                vec!["036b53093df5a932deac828cca6d663472dbc88322b05eec1d42b26ab9b16caa1c"],
-            ),
-            Provider::new(
-                ServiceKind::Exchange,
-                "OKX",
-                vec!["0294ac3e099def03c12a37e30fe5364b1223fd60069869142ef96580c8439c2e0a"],
-            ),
-            Provider::new(
-                ServiceKind::Exchange,
-                "Binance",
-                vec!["03a1f3afd646d77bdaf545cceaf079bab6057eae52c6319b63b5803d0989d6a72f"],
-            ),
-            Provider::new(
-                ServiceKind::Exchange,
-                "Bitfinex",
-                vec![
-                    "033d8656219478701227199cbd6f670335c8d408a92ae88b962c49d4dc0e83e025",
-                    "03cde60a6323f7122d5178255766e38114b4722ede08f7c9e0c5df9b912cc201d6",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "River Financial",
-                vec![
-                    "03037dc08e9ac63b82581f79b662a4d0ceca8a8ca162b1af3551595b8f2d97b70a",
-                    "03aab7e9327716ee946b8fbfae039b0db85356549e72c5cca113ea67893d0821e5",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Wallet of Satoshi",
-                vec![
-                    "0324ba2392e25bff76abd0b1f7e4b53b5f82aa53fddc3419b051b6c801db9e2247",
-                    "035e4ff418fc8b5554c5d9eea66396c227bd429a3251c8cbc711002ba215bfc226",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Alby",
-                vec![
-                    "0265791d3c9e14c69ebc2ea0f2b40c35c1a46b8db3c971bd51d1966206a42215af",
-                    "030a58b8653d32b99200a2334cfe913e51dc7d155aa0116c176657a4f1722677a3",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::BusinessWallet,
-                "lipa for Business",
-                vec![
-                    "020333076e35e398a0c14c8a0211563bbcdce5087cb300342cba09414e9b5f3605",
-                    "02ba3ad33666de22b4c22f5ff9fac0dc5d18ae9b6ce38c0a06d9e171494c39255a",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Strike.me",
-                vec![
-                    "02535215135eb832df0f9858ff775bd4ae0b8911c59e2828ff7d03b535b333e149",
-                    "027cd974e47086291bb8a5b0160a889c738f2712a703b8ea939985fd16f3aae67e",
-                    "0335e4265f783f37378e969c6a123557cf5d22cc97ec42ea3abff5dfaa64afea83",
-                    "034d7f4bbbd6c1c1d8fbe0a42dd1f59e10b66540c6872dfcaa095d8d5cffebcf46",
-                    "03b428ba4b48b524f1fa929203ddc2f0971c2077c2b89bb5b22fd83ed82ac2f7e1",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::BusinessWallet,
-                "OpenNode.com",
-                vec![
-                    "0248841dd4a94e902ede85285e67b5527afe5c46d6a3ff27955d63d18c70035757",
-                    "028d98b9969fbed53784a36617eb489a59ab6dc9b9d77fcdca9ff55307cd98e3c4",
-                    "03abf6f44c355dec0d5aa155bdbdd6e0c8fefe318eff402de65c6eb2e1be55dc3e",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Blink",
-                vec![
-                    "02fcc5bfc48e83f06c04483a2985e1c390cb0f35058baa875ad2053858b8e80dbd",
-                    "0325bb9bda523a85dc834b190289b7e25e8d92615ab2f2abffbe97983f0bb12ffb",
-                ],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "ZEBEDEE",
-                vec![
-                    "0251fff168b58b74e9b476af5a515b91fe0540a3681bc97fbb65379a807aea5f66",
-                    "02c3b0963276dc5f031a9147c3df203d6a03e194aa2934a821fa7709adc926263a",
-                    "02c3e01efd4f1944e9a50939f34cb275716fcd438769cbc0126b015677fa3b187e",
-                    "033e514ff30be0ea421f9512da0ed1aea52ea541275654d034bde3470a61269285", // klnd1
-                    "0349cb2f33d5542432b016405a22dfda18617d87abe4718e61c45909b8a5449329",
-                    "03ac0cf6da1916725f86d49ab35275b7b362054845e85c33ac181118aac266ebb7",
-                    "03b6f613e88bd874177c28c6ad83b3baba43c4c656f56be1f8df84669556054b79", // klnd0
-                    "03bf2ff8699e5528f65d41656d405c4002dd2415e4491e945fd465890bc3a9ce23",
-                    "03d506016e3e0e540ac26d557a412520ea24990ca9405d410c24122f648752b830",
-                    "03d6b14390cd178d670aa2d57c93d9519feaae7d1e34264d8bbb7932d47b75a50d",
-                ],
-            ),
-            // Cashapp
-            // Chivo (River Financial?)
-            // Other custodial wallets from https://lightningaddress.com/#providers
-        ];
-
-        let wrapping_lsp = vec![
-            voltage.clone(),
-            Provider::new(
-                ServiceKind::Exchange,
-                "Boltz",
-                vec![
-                    "026165850492521f4ac8abd9bd8088123446d126f648ca35e60f88177dc149ceb2",
-                    "02d96eadea3d780104449aca5c93461ce67c1564e2e1d73225fa67dd3b997a6018",
-                ],
-            ),
-        ];
-
-        let lsps = vec![
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "lipa", // breez.diem.lsp
-                vec!["0264a62a4307d701c04a46994ce5f5323b1ca28c80c66b73c631dbcb0990d6e835"],
-            ),
-            Provider::new(
-                ServiceKind::Lsp,
-                "c=",
-                vec!["027100442c3b79f606f80f322d98d499eefcb060599efc5d4ecb00209c2cb54190"],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Breez",
-                vec!["031015a7839468a3c266d662d5bb21ea4cea24226936e2864a7ca4f2c3939836e0"],
-            ),
-            Provider::new(
-                ServiceKind::Lsp,
-                "Breez-C",
-                vec!["02c811e575be2df47d8b48dab3d3f1c9b0f6e16d0d40b5ed78253308fc2bd7170d"],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Zeus",
-                vec!["031b301307574bbe9b9ac7b79cbe1700e31e544513eae0b5d7497483083f99e581"],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Phoenix",
-                vec!["03864ef025fde8fb587d989186ce6a4a186895ee44a926bfc370e2c366597a3f8f"],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Bitkit",
-                vec!["0296b2db342fcf87ea94d981757fdf4d3e545bd5cef4919f58b5d38dfdd73bf5c9"],
-            ),
-            Provider::new(
-                ServiceKind::ConsumerWallet,
-                "Blixt",
-                vec!["0230a5bca558e6741460c13dd34e636da28e52afd91cf93db87ed1b0392a7466eb"],
-            ),
-            voltage,
-        ];
+    pub fn new(registry: ProviderRegistry) -> RecipientDecoder {
         Self {
-            custodians,
-            wrapping_lsp,
-            lsps,
+            custodians: registry.custodians,
+            wrapping_lsp: registry.wrapping_lsps,
+            lsps: registry.lsps,
+            swap_services: registry.swap_services,
         }
     }
 
-    pub fn decode(&self, id: &String, route_hints: &Vec<Vec<Node>>) -> RecipientNode {
+    pub fn decode(
+        &self,
+        payee: &Node,
+        route_hints: &[Vec<Node>],
+        created_at: Option<NaiveDate>,
+        evidences: &mut Vec<Evidence>,
+    ) -> RecipientNode {
+        evidences.push(match payee.is_announced {
+            true => Evidence::PayeeAnnounced {
+                alias: payee.alias.clone(),
+            },
+            false => Evidence::PayeeUnannounced,
+        });
+
         if route_hints.is_empty() {
-            for custodian in &self.custodians {
-                if custodian.node_ids.contains(id) {
-                    return RecipientNode::Custodial {
-                        custodian: custodian.clone(),
-                    };
-                }
+            if let Some(custodian) = find(&self.custodians, payee, created_at, evidences) {
+                evidences.push(Evidence::PayeeIsProvider {
+                    provider: custodian.name.clone(),
+                    pubkey: payee.pubkey.clone(),
+                });
+                return RecipientNode::Custodial {
+                    custodian: custodian.clone(),
+                };
             }
-            for wrapping_lsp in &self.wrapping_lsp {
-                if wrapping_lsp.node_ids.contains(id) {
-                    return RecipientNode::NonCustodialWrapped {
-                        lsp: wrapping_lsp.clone(),
-                    };
-                }
+            if let Some(wrapping_lsp) = find(&self.wrapping_lsp, payee, created_at, evidences) {
+                evidences.push(Evidence::PayeeIsProvider {
+                    provider: wrapping_lsp.name.clone(),
+                    pubkey: payee.pubkey.clone(),
+                });
+                return RecipientNode::NonCustodialWrapped {
+                    lsp: wrapping_lsp.clone(),
+                };
+            }
+            if let Some(swap_service) = find(&self.swap_services, payee, created_at, evidences) {
+                evidences.push(Evidence::PayeeIsProvider {
+                    provider: swap_service.name.clone(),
+                    pubkey: payee.pubkey.clone(),
+                });
+                return RecipientNode::Swap {
+                    swap_service: swap_service.clone(),
+                };
             }
+            evidences.push(Evidence::PayeeIsUnknown {
+                pubkey: payee.pubkey.clone(),
+            });
         } else {
-            // TODO: Check that the node does not have announced channels.
             for hint in route_hints {
-                if hint.len() == 1 {
-                    if let Some(hop) = hint.first() {
-                        for lsp in &self.lsps {
-                            if lsp.node_ids.contains(&hop.pubkey) {
-                                return RecipientNode::NonCustodial {
-                                    id: id.clone(),
-                                    lsp: lsp.clone(),
-                                };
-                            }
-                        }
-                    }
-                } else {
-                    return RecipientNode::Unknown;
+                if let Some(recipient) = self.decode_route_hint(payee, hint, created_at, evidences)
+                {
+                    return recipient;
+                }
+            }
+            if let Some(entry_node) = shared_entry_node(route_hints) {
+                // A single hint has nothing to be consistent with, it is only a lead.
+                if route_hints.len() > 1 {
+                    evidences.push(Evidence::SharedEntryNode {
+                        pubkey: entry_node.pubkey.clone(),
+                        alias: entry_node.alias.clone(),
+                        hints: route_hints.len(),
+                    });
                 }
+                return RecipientNode::SharedEntryNode {
+                    id: payee.pubkey.clone(),
+                    pubkey: entry_node.pubkey.clone(),
+                    alias: entry_node.alias.clone(),
+                };
             }
-            // TODO: If all hints start from the same node, return node alias.
-            // TODO: Compute confidence as amount of sats in days locked in announced channels.
         }
 
         RecipientNode::Unknown
     }
+
+    /// Recognises route hints of an LDK phantom invoice.
+    ///
+    /// Every hint ends with a hop from a real node to the phantom payee without fees and with
+    /// the minimal CLTV expiry delta of LDK.
+    pub fn decode_phantom(
+        &self,
+        payee: &Node,
+        route_hints: &[Vec<Hop>],
+        created_at: Option<NaiveDate>,
+        evidences: &mut Vec<Evidence>,
+    ) -> Option<RecipientNode> {
+        if payee.is_announced || route_hints.is_empty() {
+            return None;
+        }
+        let is_phantom_hop = |hop: &Hop| {
+            hop.base_fee_msat == 0
+                && hop.fee_proportional_millionths == 0
+                && hop.cltv_expiry_delta == LDK_MIN_CLTV_EXPIRY_DELTA
+                && hop.htlc_minimum_msat.is_none()
+                && hop.htlc_maximum_msat.is_none()
+        };
+        if !route_hints
+            .iter()
+            .all(|hint| hint.last().is_some_and(is_phantom_hop))
+        {
+            return None;
+        }
+
+        let mut real_nodes: Vec<Node> = Vec::new();
+        for hop in route_hints.iter().filter_map(|hint| hint.last()) {
+            if !real_nodes.contains(&hop.node) {
+                real_nodes.push(hop.node.clone());
+            }
+        }
+        if real_nodes.len() < 2 {
+            return None;
+        }
+
+        evidences.push(Evidence::PhantomRouteHints {
+            real_nodes: real_nodes.len(),
+        });
+        let mut provider = None;
+        for node in &real_nodes {
+            let found = find(&self.custodians, node, created_at, evidences)
+                .or_else(|| find(&self.lsps, node, created_at, evidences));
+            if let Some(found) = found {
+                evidences.push(Evidence::PhantomRealNodeIsProvider {
+                    provider: found.name.clone(),
+                    pubkey: node.pubkey.clone(),
+                });
+                provider.get_or_insert(found);
+            }
+        }
+        Some(RecipientNode::PhantomNode {
+            id: payee.pubkey.clone(),
+            real_nodes,
+            provider: provider.cloned(),
+        })
+    }
+
+    /// Recognises invoices of Fedimint federations behind a gateway which is not registered.
+    ///
+    /// Gateways put the index of the federation instead of a real SCID into the route hint,
+    /// so the SCID points to the genesis block.
+    pub fn decode_ecash_gateway(
+        &self,
+        payee: &Node,
+        route_hints: &[Vec<Hop>],
+        created_at: Option<NaiveDate>,
+        evidences: &mut Vec<Evidence>,
+    ) -> Option<RecipientNode> {
+        let [hint] = route_hints else {
+            return None;
+        };
+        let [hop] = hint.as_slice() else {
+            return None;
+        };
+        if hop.short_channel_id.block_height != 0 || self.is_known(&hop.node.pubkey, created_at) {
+            return None;
+        }
+        let is_fedimint_policy = hop.base_fee_msat == FEDIMINT_BASE_FEE_MSAT
+            && hop.fee_proportional_millionths == FEDIMINT_FEE_PROPORTIONAL_MILLIONTHS
+            && hop.cltv_expiry_delta == FEDIMINT_CLTV_EXPIRY_DELTA
+            && hop.htlc_minimum_msat.is_none()
+            && hop.htlc_maximum_msat.is_none();
+        if !is_fedimint_policy {
+            return None;
+        }
+
+        let scid = &hop.short_channel_id;
+        let federation_index =
+            u64::from(scid.transaction_index) << 16 | u64::from(scid.output_index);
+        evidences.push(Evidence::FederationIndexScid {
+            pubkey: hop.node.pubkey.clone(),
+            scid: hop.short_channel_id.to_string(),
+        });
+        evidences.push(Evidence::FedimintRouteHintPolicy {
+            pubkey: hop.node.pubkey.clone(),
+        });
+        Some(RecipientNode::EcashGateway {
+            id: payee.pubkey.clone(),
+            pubkey: hop.node.pubkey.clone(),
+            alias: hop.node.alias.clone(),
+            federation_index,
+        })
+    }
+
+    fn is_known(&self, pubkey: &String, created_at: Option<NaiveDate>) -> bool {
+        [
+            &self.custodians,
+            &self.wrapping_lsp,
+            &self.lsps,
+            &self.swap_services,
+        ]
+        .into_iter()
+        .any(|providers| lookup(providers, pubkey, created_at).is_some())
+    }
+
+    fn decode_route_hint(
+        &self,
+        payee: &Node,
+        hint: &[Node],
+        created_at: Option<NaiveDate>,
+        evidences: &mut Vec<Evidence>,
+    ) -> Option<RecipientNode> {
+        // The first public hop is usually the routing node of the LSP.
+        let first_public = hint.iter().position(|hop| hop.is_announced);
+        let positions = first_public
+            .into_iter()
+            .chain((0..hint.len()).filter(|i| Some(*i) != first_public));
+        for position in positions {
+            let hop = &hint[position];
+            let (provider, recipient) =
+                if let Some(lsp) = find(&self.lsps, hop, created_at, evidences) {
+                    // A node with announced channels is not a wallet hidden behind the LSP.
+                    let recipient = match payee.is_announced {
+                        true => RecipientNode::PublicRoutingNode {
+                            id: payee.pubkey.clone(),
+                            alias: payee.alias.clone(),
+                            lsp: lsp.clone(),
+                        },
+                        false => RecipientNode::NonCustodial {
+                            id: payee.pubkey.clone(),
+                            lsp: lsp.clone(),
+                        },
+                    };
+                    (lsp, recipient)
+                } else if let Some(custodian) = find(&self.custodians, hop, created_at, evidences) {
+                    let recipient = RecipientNode::Custodial {
+                        custodian: custodian.clone(),
+                    };
+                    (custodian, recipient)
+                } else {
+                    continue;
+                };
+            evidences.push(Evidence::RouteHintFromProvider {
+                provider: provider.name.clone(),
+                pubkey: hop.pubkey.clone(),
+                position: position + 1,
+                hops: hint.len(),
+            });
+            return Some(recipient);
+        }
+
+        match hint {
+            [hop] => evidences.push(Evidence::RouteHintFromUnknownNode {
+                pubkey: hop.pubkey.clone(),
+            }),
+            _ => evidences.push(Evidence::MultiHopRouteHint { hops: hint.len() }),
+        }
+        None
+    }
+
+    pub fn decode_blinded_path(
+        &self,
+        id: &str,
+        introduction_node: &Node,
+        created_at: Option<NaiveDate>,
+        evidences: &mut Vec<Evidence>,
+    ) -> RecipientNode {
+        let pubkey = &introduction_node.pubkey;
+        if let Some(custodian) = find(&self.custodians, introduction_node, created_at, evidences) {
+            evidences.push(Evidence::IntroductionNodeIsProvider {
+                provider: custodian.name.clone(),
+                pubkey: pubkey.clone(),
+            });
+            return RecipientNode::Custodial {
+                custodian: custodian.clone(),
+            };
+        }
+        if let Some(wrapping_lsp) =
+            find(&self.wrapping_lsp, introduction_node, created_at, evidences)
+        {
+            evidences.push(Evidence::IntroductionNodeIsProvider {
+                provider: wrapping_lsp.name.clone(),
+                pubkey: pubkey.clone(),
+            });
+            return RecipientNode::NonCustodialWrapped {
+                lsp: wrapping_lsp.clone(),
+            };
+        }
+        if let Some(swap_service) = find(
+            &self.swap_services,
+            introduction_node,
+            created_at,
+            evidences,
+        ) {
+            evidences.push(Evidence::IntroductionNodeIsProvider {
+                provider: swap_service.name.clone(),
+                pubkey: pubkey.clone(),
+            });
+            return RecipientNode::Swap {
+                swap_service: swap_service.clone(),
+            };
+        }
+        if let Some(lsp) = find(&self.lsps, introduction_node, created_at, evidences) {
+            evidences.push(Evidence::BlindedPathFromProvider {
+                provider: lsp.name.clone(),
+                pubkey: pubkey.clone(),
+            });
+            return RecipientNode::NonCustodial {
+                id: id.to_string(),
+                lsp: lsp.clone(),
+            };
+        }
+        evidences.push(Evidence::IntroductionNodeIsUnknown {
+            pubkey: pubkey.clone(),
+        });
+        RecipientNode::Unknown
+    }
+}
+
+/// Attributes an unknown recipient to a swap if the invoice leaves room for an on-chain lockup.
+///
+/// The delta must exceed the Breez SDK default, which alone would make every Breez SDK wallet
+/// look like a swap.
+pub(crate) fn decode_unknown_swap(
+    recipient: RecipientNode,
+    details: &InvoiceDetails,
+) -> RecipientNode {
+    match recipient {
+        RecipientNode::Unknown
+            if details.min_final_cltv_expiry_delta > BREEZ_SDK_MIN_FINAL_CLTV_EXPIRY_DELTA =>
+        {
+            RecipientNode::UnknownSwap {
+                min_final_cltv_expiry_delta: details.min_final_cltv_expiry_delta,
+            }
+        }
+        recipient => recipient,
+    }
 }
 
-// #[cfg(test)]
-// mod tests {
-//     use super::*;
-//     use breez_sdk_core::parse_invoice;
-
-//     fn decode(invoice: &str) -> String {
-//         let decoder = RecipientDecoder::new();
-//         let invoice = parse_invoice(invoice).expect("Invalid invoice");
-//         match decoder.decode(&invoice) {
-//             RecipientNode::Custodial { custodian } => custodian.name,
-//             RecipientNode::NonCustodial { id, lsp } => format!("{id}@{}", lsp.name),
-//             RecipientNode::NonCustodialWrapped { lsp } => format!("wrapped@{}", lsp.name),
-//             RecipientNode::Unknown => "unknown".to_string(),
-//         }
-//     }
-
-//     #[test]
-//     fn test_decode_recipient() {
-//         let invoice = "lnbc1431800n1pjcgm4epp5hxr22je783fzcr37d4xp0gn5042pnz48u79lnvj76quu36nv0gmshp5fp66r97zwxcrs33jcc8l6rr3803rp8z3h30pevqevt0fqp203p7scqzzsxqyz5vqsp5jg45hhcmchvagsa8fn05nkyptp99cazgtvgchjcs5j3v7xu53rcq9qyyssq6m74fcnv704y0k2e50sqp6wc7wjhxmrhtjndyzutgzw8rplk8w3yg07wdraur2qh37wj67xkcwrv238s965dfdn90vfj75hm65xyf2sppxh5xw";
-//         assert_eq!(decode(invoice), "Alby");
-
-//         let invoice = "lnbc1u1pj62kd6pp557unu8u02cg7nqnsj5rnrgsrzctw7f85g9wr6wu3hhwa5qacmhtqdqqcqzzsxqyz5vqsp5arf47cesn7xyjc7wgq7fl288rczl45j4wql5un4tam8jcuchmh2s9qyyssqmzxkcqk9cpau6fu6zv5n5rz9znuuwwevxz073y8f37yv3qrpp3dpwhruf47206q3rv2st2d7jc2v8nxy7pa6ad7s8rsh9zzq5g33t3qq7d5huu";
-//         assert_eq!(decode(invoice), "Wallet of Satoshi");
-
-//         let invoice = "lnbc11110n1pj6tvs8pp5dxdctpprs7qw82etw4m9ecgjsq9u2ns85uw9vyvqczd7j492wsasdqqcqzzsxqyz5vqsp5y7v5wt5z7f5nk87dzv6vle4xtvkcfu8rlp2ryg3alhhptwwh3uws9qyyssqffdk8ufx8ezvpl292td2tfp9y7r8vyjxmfgdzwqm35gdtd9fyfgqhwz5wftvr6m2fa30a6hvhk4lcduq4wf09h0yxt5u4sucyz6rsegqknr266";
-//         assert_eq!(decode(invoice), "unknown");
-
-//         // Non-custodial.
-
-//         let invoice = "lnbc120n1pjcxr98dp923jhxarfdenjqur9dejxjmn8ypcxz7tdv4h8gpp5p0547ufczxajsnzwylyw082p2mz6cwswmr0z0uyhmgpfn06gc7tqxqrrsssp546n87knlt8hedp9cp30rkgtcduw2hrr00ex62msawwzfqszh0k7s9qrsgqcqzysrzjqfj2v2jrqltsrsz2g6v5ee04xga3eg5vsrrxku7xx8dukzvs6m5r2avk07w5uftf4sqqqqlgqqqqqzsqygs6sp6j4mwstpvjd648cmtndazpnfvhnsh9ff8frgrkmx3jarm0vxyqf822a2d9sefxzyqwlm5epvtcyj5rjpu09lsy4jffu7t0a7xxgqpzsw6v";
-//         assert_eq!(
-//             decode(invoice),
-//             "02c4d6599009cfc6a015562252ad7b14b8a4ed2640aeb69b688c215e3b4ceb5a99@lipa"
-//         );
-
-//         let invoice = "lnbc50n1pj62uuqpp5p447yvxk5cjflk685kl53eg3xxz4pp5m5356akn486ez0356p3csdqggfex2et6cqzzsxqrrssrzjqvgptfurj3528snx6e3dtwepafxw5fpzdymw9pj20jj09sunnqmwpapyqqqqqqqltqqqqqlgqqqqqqgq9qsp5jwllzl5nk8q7890qwyyprj9hxgey4hwsph7sq66wdd4p7v7t6pgq9qyyssqypk7z3rar8gnfype6mxsc92ccax49huemm2nnphx3qkhm53hflth6k8t577exmuqsxp5fm7evzpw5v5d3g3004ljh37v58t8wrcchagp063dqm";
-//         assert_eq!(
-//             decode(invoice),
-//             "039fdb76c4ef865649376aeb0b8d1cb71fa12b2712c18eecb7cb03364786f657aa@Breez"
-//         );
-
-//         let invoice = "lnbc5m1pj6tgnhsp5gsfazhx0c5gfcfmxh38ag5lyrshk9h4djzrejldvmfe49vxpyvyqpp59p000w04t5xhc9ch7lj909wtlqmgrcjxymcnnn4gc9xmux7cgnrsdqqnp4qwh05slmksqfkgdyz2wst9fewjmah2amldg3jg2pqzqgvr723mslqxqrrsxcqzzn9qyysgqcd2avdg6gt7j24tjycz0r38xr5r809tczelvyjr52cgy32z7nzs9wsmdxxws4xx8s7s8vv3w5qgfslcg608vj0ys2dqvqg227m75dwcq6z898f";
-//         assert_eq!(decode(invoice), "wrapped@Voltage Flow 2.0");
-//     }
-// }
+/// Swaps lock funds on-chain, so their invoices leave room for a confirmation.
+pub(crate) fn swap_traits(details: &InvoiceDetails, evidences: &mut Vec<Evidence>) {
+    if details.min_final_cltv_expiry_delta >= SWAP_MIN_FINAL_CLTV_EXPIRY_DELTA {
+        evidences.push(Evidence::LongFinalCltvExpiryDelta {
+            blocks: details.min_final_cltv_expiry_delta,
+        });
+    }
+}
+
+/// Traits of an announced payee visible from its node announcement.
+pub(crate) fn payee_traits(payee: &Node, evidences: &mut Vec<Evidence>) {
+    if !payee.is_announced {
+        return;
+    }
+    if payee.is_tor_only() {
+        evidences.push(Evidence::PayeeTorOnly);
+    }
+    if payee.is_stale {
+        evidences.push(Evidence::PayeeStale {
+            last_update: payee.last_update,
+        });
+    }
+    if payee.is_major_routing_node() {
+        evidences.push(Evidence::PayeeMajorRoutingNode {
+            channels: payee.channels,
+        });
+    }
+}
+
+fn shared_entry_node(route_hints: &[Vec<Node>]) -> Option<&Node> {
+    let entry_node = route_hints.first()?.first()?;
+    route_hints
+        .iter()
+        .all(|hint| hint.first().map(|node| &node.pubkey) == Some(&entry_node.pubkey))
+        .then_some(entry_node)
+}
+
+/// Finds the provider operating the node when the invoice was created.
+///
+/// Nodes of a provider without attributions are matched at any time. A node retired by the
+/// time of the invoice is matched only if no provider operated it then, and is flagged.
+/// Unlisted nodes are attributed through other nodes of their operator cluster, unless the
+/// cluster holds nodes of several providers.
+fn find<'a>(
+    providers: &'a [Provider],
+    node: &Node,
+    created_at: Option<NaiveDate>,
+    evidences: &mut Vec<Evidence>,
+) -> Option<&'a Provider> {
+    let (provider, retired, pubkey) = match lookup(providers, &node.pubkey, created_at) {
+        Some((provider, retired)) => (provider, retired, &node.pubkey),
+        None => {
+            let mut matches = node.operator_nodes.iter().filter_map(|pubkey| {
+                lookup(providers, pubkey, created_at)
+                    .map(|(provider, retired)| (provider, retired, pubkey))
+            });
+            let (provider, retired, pubkey) = matches.next()?;
+            if matches.any(|(other, ..)| other.name != provider.name) {
+                return None;
+            }
+            (provider, retired, pubkey)
+        }
+    };
+    if pubkey != &node.pubkey {
+        evidences.push(Evidence::SameOperatorAsProvider {
+            provider: provider.name.clone(),
+            pubkey: node.pubkey.clone(),
+            provider_node: pubkey.clone(),
+        });
+    }
+    if let Some(attribution) = retired {
+        evidences.push(Evidence::RetiredProviderNode {
+            provider: provider.name.clone(),
+            pubkey: pubkey.clone(),
+            until: attribution.active_until,
+        });
+    }
+    Some(provider)
+}
+
+/// Returns the matching provider and the attribution of the node if it was retired.
+fn lookup<'a>(
+    providers: &'a [Provider],
+    pubkey: &String,
+    created_at: Option<NaiveDate>,
+) -> Option<(&'a Provider, Option<&'a Attribution>)> {
+    let mut retired = None;
+    for provider in providers.iter().filter(|p| p.node_ids.contains(pubkey)) {
+        let Some(created_at) = created_at else {
+            return Some((provider, None));
+        };
+        let mut attributions = provider
+            .attributions
+            .iter()
+            .filter(|attribution| &attribution.node_id == pubkey)
+            .peekable();
+        if attributions.peek().is_none() {
+            return Some((provider, None));
+        }
+        let mut retired_attribution = None;
+        for attribution in attributions {
+            if attribution.is_active(created_at) {
+                return Some((provider, None));
+            }
+            if attribution.is_retired(created_at) {
+                retired_attribution.get_or_insert(attribution);
+            }
+        }
+        if retired.is_none() {
+            retired = retired_attribution.map(|attribution| (provider, Some(attribution)));
+        }
+    }
+    retired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confidence::ConfidenceLevel;
+    use crate::offer_details::ShortChannelId;
+    use crate::InvoiceDetective;
+
+    fn node(pubkey: &str, is_announced: bool) -> Node {
+        Node {
+            pubkey: pubkey.to_string(),
+            is_announced,
+            ..Default::default()
+        }
+    }
+
+    /// Hop without fees and with the CLTV expiry delta of LDK phantom route hints.
+    fn hop(pubkey: &str, short_channel_id: u64) -> Hop {
+        Hop {
+            node: node(pubkey, true),
+            short_channel_id: ShortChannelId::from(short_channel_id),
+            base_fee_msat: 0,
+            fee_proportional_millionths: 0,
+            cltv_expiry_delta: LDK_MIN_CLTV_EXPIRY_DELTA,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: None,
+            channel: None,
+            channel_age: None,
+            public_policy: None,
+        }
+    }
+
+    fn decode(invoice: &str) -> String {
+        let invoice_detective = InvoiceDetective::builder()
+            .in_memory_graph()
+            .build()
+            .unwrap();
+        let findings = invoice_detective
+            .investigate(invoice)
+            .expect("Invalid invoice");
+        match findings.recipient {
+            RecipientNode::Custodial { custodian } => custodian.name,
+            RecipientNode::NonCustodial { id, lsp } => format!("{id}@{}", lsp.name),
+            RecipientNode::NonCustodialWrapped { lsp } => format!("wrapped@{}", lsp.name),
+            RecipientNode::Swap { swap_service } => format!("swap@{}", swap_service.name),
+            RecipientNode::UnknownSwap { .. } => "swap@unknown".to_string(),
+            RecipientNode::PublicRoutingNode { id, lsp, .. } => format!("public:{id}@{}", lsp.name),
+            RecipientNode::PhantomNode { real_nodes, .. } => {
+                format!("phantom:{}", real_nodes.len())
+            }
+            RecipientNode::EcashGateway { pubkey, .. } => format!("gateway:{pubkey}"),
+            RecipientNode::SharedEntryNode { pubkey, .. } => format!("entry:{pubkey}"),
+            RecipientNode::Ambiguous { .. } => "ambiguous".to_string(),
+            RecipientNode::Unknown => "unknown".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_decode_recipient() {
+        let invoice = "lnbc1431800n1pjcgm4epp5hxr22je783fzcr37d4xp0gn5042pnz48u79lnvj76quu36nv0gmshp5fp66r97zwxcrs33jcc8l6rr3803rp8z3h30pevqevt0fqp203p7scqzzsxqyz5vqsp5jg45hhcmchvagsa8fn05nkyptp99cazgtvgchjcs5j3v7xu53rcq9qyyssq6m74fcnv704y0k2e50sqp6wc7wjhxmrhtjndyzutgzw8rplk8w3yg07wdraur2qh37wj67xkcwrv238s965dfdn90vfj75hm65xyf2sppxh5xw";
+        assert_eq!(decode(invoice), "Alby");
+
+        let invoice = "lnbc1u1pj62kd6pp557unu8u02cg7nqnsj5rnrgsrzctw7f85g9wr6wu3hhwa5qacmhtqdqqcqzzsxqyz5vqsp5arf47cesn7xyjc7wgq7fl288rczl45j4wql5un4tam8jcuchmh2s9qyyssqmzxkcqk9cpau6fu6zv5n5rz9znuuwwevxz073y8f37yv3qrpp3dpwhruf47206q3rv2st2d7jc2v8nxy7pa6ad7s8rsh9zzq5g33t3qq7d5huu";
+        assert_eq!(decode(invoice), "Wallet of Satoshi");
+
+        let invoice = "lnbc11110n1pj6tvs8pp5dxdctpprs7qw82etw4m9ecgjsq9u2ns85uw9vyvqczd7j492wsasdqqcqzzsxqyz5vqsp5y7v5wt5z7f5nk87dzv6vle4xtvkcfu8rlp2ryg3alhhptwwh3uws9qyyssqffdk8ufx8ezvpl292td2tfp9y7r8vyjxmfgdzwqm35gdtd9fyfgqhwz5wftvr6m2fa30a6hvhk4lcduq4wf09h0yxt5u4sucyz6rsegqknr266";
+        assert_eq!(decode(invoice), "unknown");
+
+        // Non-custodial.
+
+        let invoice = "lnbc120n1pjcxr98dp923jhxarfdenjqur9dejxjmn8ypcxz7tdv4h8gpp5p0547ufczxajsnzwylyw082p2mz6cwswmr0z0uyhmgpfn06gc7tqxqrrsssp546n87knlt8hedp9cp30rkgtcduw2hrr00ex62msawwzfqszh0k7s9qrsgqcqzysrzjqfj2v2jrqltsrsz2g6v5ee04xga3eg5vsrrxku7xx8dukzvs6m5r2avk07w5uftf4sqqqqlgqqqqqzsqygs6sp6j4mwstpvjd648cmtndazpnfvhnsh9ff8frgrkmx3jarm0vxyqf822a2d9sefxzyqwlm5epvtcyj5rjpu09lsy4jffu7t0a7xxgqpzsw6v";
+        assert_eq!(
+            decode(invoice),
+            "02c4d6599009cfc6a015562252ad7b14b8a4ed2640aeb69b688c215e3b4ceb5a99@lipa"
+        );
+
+        let invoice = "lnbc50n1pj62uuqpp5p447yvxk5cjflk685kl53eg3xxz4pp5m5356akn486ez0356p3csdqggfex2et6cqzzsxqrrssrzjqvgptfurj3528snx6e3dtwepafxw5fpzdymw9pj20jj09sunnqmwpapyqqqqqqqltqqqqqlgqqqqqqgq9qsp5jwllzl5nk8q7890qwyyprj9hxgey4hwsph7sq66wdd4p7v7t6pgq9qyyssqypk7z3rar8gnfype6mxsc92ccax49huemm2nnphx3qkhm53hflth6k8t577exmuqsxp5fm7evzpw5v5d3g3004ljh37v58t8wrcchagp063dqm";
+        assert_eq!(
+            decode(invoice),
+            "039fdb76c4ef865649376aeb0b8d1cb71fa12b2712c18eecb7cb03364786f657aa@Breez"
+        );
+
+        let invoice = "lnbc5m1pj6tgnhsp5gsfazhx0c5gfcfmxh38ag5lyrshk9h4djzrejldvmfe49vxpyvyqpp59p000w04t5xhc9ch7lj909wtlqmgrcjxymcnnn4gc9xmux7cgnrsdqqnp4qwh05slmksqfkgdyz2wst9fewjmah2amldg3jg2pqzqgvr723mslqxqrrsxcqzzn9qyysgqcd2avdg6gt7j24tjycz0r38xr5r809tczelvyjr52cgy32z7nzs9wsmdxxws4xx8s7s8vv3w5qgfslcg608vj0ys2dqvqg227m75dwcq6z898f";
+        assert_eq!(decode(invoice), "wrapped@Voltage Flow 2.0");
+
+        // Aqua swaps to Liquid via Boltz.
+        let invoice = "lnbc100u1pj6ljjxsp5mk2e0n7vjcuuc752ljnm4zve23a3c3kdq5qxpz5pane0k5dqzjespp53zxjj3sug24tj6wlwlrlfu8k4676s55kj4cr2lw6mntnhk4mh8tsdpz2djkuepqw3hjqnpdgf2yxgrpv3j8yetnwvxqyp2xqcqz959qxpqysgqp43g45mqyk00cj7580dyz8gaevj6dcg7chmjz3gcmdwkcfv0qm5rt4h62fnvxng6nyweapfs7cc5rafne2mqvkun69lxjrus6gvvawspzs6740";
+        assert_eq!(decode(invoice), "swap@Boltz");
+    }
+
+    #[test]
+    fn test_decode_multi_hop_route_hint() {
+        let decoder = RecipientDecoder::new(ProviderRegistry::default());
+        let phoenix = "03864ef025fde8fb587d989186ce6a4a186895ee44a926bfc370e2c366597a3f8f";
+        let payee = node("payee", false);
+        let hints = [vec![node("routing", false), node(phoenix, true)]];
+
+        let mut evidences = Vec::new();
+        let recipient = decoder.decode(&payee, &hints, None, &mut evidences);
+        assert_eq!(recipient.provider().unwrap().name, "Phoenix");
+        assert!(evidences.contains(&Evidence::RouteHintFromProvider {
+            provider: "Phoenix".to_string(),
+            pubkey: phoenix.to_string(),
+            position: 2,
+            hops: 2,
+        }));
+
+        let payee = node("payee", true);
+        let recipient = decoder.decode(&payee, &hints, None, &mut Vec::new());
+        assert!(matches!(recipient, RecipientNode::PublicRoutingNode { .. }));
+
+        let hints = [
+            vec![node("routing", true), node("other", false)],
+            vec![node("routing", true)],
+        ];
+        let recipient = decoder.decode(&payee, &hints, None, &mut Vec::new());
+        assert!(
+            matches!(recipient, RecipientNode::SharedEntryNode { pubkey, .. } if pubkey == "routing")
+        );
+
+        let hints = [vec![node("routing", true)], vec![node("other", true)]];
+        let recipient = decoder.decode(&payee, &hints, None, &mut Vec::new());
+        assert_eq!(recipient, RecipientNode::Unknown);
+
+        let hints = [vec![node("routing", true)]];
+        let recipient = decoder.decode(&payee, &hints, None, &mut Vec::new());
+        assert!(
+            matches!(&recipient, RecipientNode::SharedEntryNode { pubkey, .. } if pubkey == "routing")
+        );
+        let confidence = crate::confidence::assess(&recipient, &payee, &hints, None);
+        assert_eq!(confidence.level, ConfidenceLevel::Low);
+        assert_eq!(
+            confidence.rationale,
+            "Single route hint enters through routing, which is not a known provider"
+        );
+    }
+
+    #[test]
+    fn test_decode_phantom() {
+        let decoder = RecipientDecoder::new(ProviderRegistry::default());
+        let pubkeys = [
+            "0324ba2392e25bff76abd0b1f7e4b53b5f82aa53fddc3419b051b6c801db9e2247",
+            "035e4ff418fc8b5554c5d9eea66396c227bd429a3251c8cbc711002ba215bfc226",
+        ];
+        let payee = node("phantom", false);
+
+        let route_hints = pubkeys.map(|pubkey| vec![hop(pubkey, 42)]);
+        let recipient = decoder
+            .decode_phantom(&payee, &route_hints, None, &mut Vec::new())
+            .unwrap();
+        assert_eq!(recipient.provider().unwrap().name, "Wallet of Satoshi");
+
+        let route_hints = pubkeys.map(|pubkey| {
+            vec![Hop {
+                base_fee_msat: 1000,
+                ..hop(pubkey, 42)
+            }]
+        });
+        let recipient = decoder.decode_phantom(&payee, &route_hints, None, &mut Vec::new());
+        assert_eq!(recipient, None);
+    }
+
+    #[test]
+    fn test_decode_ecash_gateway() {
+        let decoder = RecipientDecoder::new(ProviderRegistry::default());
+        let payee = node("payee", false);
+
+        let gateway = |short_channel_id| Hop {
+            fee_proportional_millionths: FEDIMINT_FEE_PROPORTIONAL_MILLIONTHS,
+            cltv_expiry_delta: FEDIMINT_CLTV_EXPIRY_DELTA,
+            ..hop("gateway", short_channel_id)
+        };
+        let route_hints = [vec![gateway(3)]];
+        let recipient = decoder.decode_ecash_gateway(&payee, &route_hints, None, &mut Vec::new());
+        assert!(matches!(
+            recipient,
+            Some(RecipientNode::EcashGateway {
+                federation_index: 3,
+                ..
+            })
+        ));
+
+        // A free hop with the LDK delta is not a Fedimint gateway.
+        let route_hints = [vec![hop("gateway", 3)]];
+        let recipient = decoder.decode_ecash_gateway(&payee, &route_hints, None, &mut Vec::new());
+        assert_eq!(recipient, None);
+
+        let route_hints = [vec![gateway(840_000 << 40)]];
+        let recipient = decoder.decode_ecash_gateway(&payee, &route_hints, None, &mut Vec::new());
+        assert_eq!(recipient, None);
+
+        // Registered gateways are decoded as custodians.
+        let fedi = "02dd3fcdaa17b9bc83bf7138fbea85d0e83385a68b5fc8f9933658c8ee04644f68";
+        let route_hints = [vec![Hop {
+            node: node(fedi, true),
+            ..gateway(3)
+        }]];
+        let recipient = decoder.decode_ecash_gateway(&payee, &route_hints, None, &mut Vec::new());
+        assert_eq!(recipient, None);
+        let recipient = decoder.decode(
+            &payee,
+            &[vec![route_hints[0][0].node.clone()]],
+            None,
+            &mut Vec::new(),
+        );
+        assert_eq!(
+            recipient.provider().unwrap().service,
+            ServiceKind::EcashMint
+        );
+    }
+
+    #[test]
+    fn test_validity_window() {
+        let registry = ProviderRegistry::from_toml(
+            r#"
+            version = 1
+
+            [[providers]]
+            name = "Old"
+            service = "ConsumerWallet"
+            role = "custodian"
+            node_ids = ["02aaaa", "02bbbb"]
+
+            [[providers.attributions]]
+            node_id = "02aaaa"
+            source = "https://old.example"
+            active_until = "2023-12-31"
+
+            [[providers.attributions]]
+            node_id = "02bbbb"
+            source = "https://old.example"
+            active_until = "2023-12-31"
+
+            [[providers]]
+            name = "New"
+            service = "ConsumerWallet"
+            role = "custodian"
+            node_ids = ["02aaaa"]
+
+            [[providers.attributions]]
+            node_id = "02aaaa"
+            source = "https://new.example"
+            active_from = "2024-01-01"
+        "#,
+        )
+        .unwrap();
+        let decoder = RecipientDecoder::new(registry);
+        let name = |pubkey, date, evidences: &mut Vec<Evidence>| {
+            let recipient = decoder.decode(&node(pubkey, false), &[], date, evidences);
+            recipient.provider().map(|provider| provider.name.clone())
+        };
+
+        let mut evidences = Vec::new();
+        let before = NaiveDate::from_ymd_opt(2023, 6, 1);
+        let after = NaiveDate::from_ymd_opt(2024, 6, 1);
+        assert_eq!(name("02aaaa", before, &mut evidences).unwrap(), "Old");
+        assert_eq!(name("02aaaa", after, &mut evidences).unwrap(), "New");
+        assert_eq!(name("02aaaa", None, &mut evidences).unwrap(), "Old");
+        assert!(!evidences
+            .iter()
+            .any(|e| matches!(e, Evidence::RetiredProviderNode { .. })));
+
+        assert_eq!(name("02bbbb", after, &mut evidences).unwrap(), "Old");
+        assert!(evidences.contains(&Evidence::RetiredProviderNode {
+            provider: "Old".to_string(),
+            pubkey: "02bbbb".to_string(),
+            until: NaiveDate::from_ymd_opt(2023, 12, 31),
+        }));
+    }
+
+    #[test]
+    fn test_decode_operator_cluster() {
+        let decoder = RecipientDecoder::new(ProviderRegistry::default());
+        let wos = "035e4ff418fc8b5554c5d9eea66396c227bd429a3251c8cbc711002ba215bfc226";
+        let payee = Node {
+            operator_nodes: vec![wos.to_string()],
+            ..node("payee", true)
+        };
+
+        let mut evidences = Vec::new();
+        let recipient = decoder.decode(&payee, &[], None, &mut evidences);
+        assert_eq!(recipient.provider().unwrap().name, "Wallet of Satoshi");
+        assert!(evidences.contains(&Evidence::SameOperatorAsProvider {
+            provider: "Wallet of Satoshi".to_string(),
+            pubkey: "payee".to_string(),
+            provider_node: wos.to_string(),
+        }));
+        let confidence = crate::confidence::assess(&recipient, &payee, &[], None);
+        assert_eq!(confidence.level, ConfidenceLevel::Medium);
+
+        let phoenix = "03864ef025fde8fb587d989186ce6a4a186895ee44a926bfc370e2c366597a3f8f";
+        let entry = Node {
+            operator_nodes: vec![phoenix.to_string()],
+            ..node("entry", true)
+        };
+        let payee = node("payee", false);
+        let hints = [vec![entry]];
+        let recipient = decoder.decode(&payee, &hints, None, &mut Vec::new());
+        assert!(matches!(recipient, RecipientNode::NonCustodial { .. }));
+        let confidence = crate::confidence::assess(&recipient, &payee, &hints, None);
+        assert_eq!(confidence.level, ConfidenceLevel::Medium);
+        assert_eq!(
+            confidence.rationale,
+            "Route hint node is attributed to Phoenix only through its operator cluster"
+        );
+
+        // A cluster with nodes of several providers is not attributed.
+        let kraken = "02f1a8c87607f415c8f22c00593002775941dea48869ce23096af27b0cfdcc0b69";
+        let payee = Node {
+            operator_nodes: vec![kraken.to_string(), wos.to_string()],
+            ..node("payee", true)
+        };
+        let recipient = decoder.decode(&payee, &[], None, &mut Vec::new());
+        assert_eq!(recipient.provider(), None);
+    }
+}
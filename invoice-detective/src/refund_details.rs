@@ -0,0 +1,44 @@
+use crate::chain_hash::ChainHash;
+use crate::offer_details::{Amount, BlindedPath};
+use bitcoin::hex::DisplayHex;
+use chrono::{DateTime, Utc};
+use lightning::offers::refund::Refund;
+
+#[derive(Debug)]
+pub struct RefundDetails {
+    pub chain: String,
+    pub amount: Amount,
+    pub quantity: Option<u64>,
+    pub description: String,
+    pub issuer: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub payer_id: String,
+    pub payer_note: Option<String>,
+    pub payer_metadata: String,
+    pub paths: Vec<BlindedPath>,
+}
+
+impl From<Refund> for RefundDetails {
+    fn from(refund: Refund) -> Self {
+        let amount = lightning::offers::offer::Amount::Bitcoin {
+            amount_msats: refund.amount_msats(),
+        };
+        let expires_at = refund
+            .absolute_expiry()
+            .and_then(|d| DateTime::from_timestamp(d.as_secs() as i64, 0));
+        let paths = refund.paths().iter().map(BlindedPath::from).collect();
+
+        Self {
+            chain: ChainHash::from(refund.chain()).to_string(),
+            amount: Amount::from(amount),
+            quantity: refund.quantity(),
+            description: refund.description().to_string(),
+            issuer: refund.issuer().map(|s| s.to_string()),
+            expires_at,
+            payer_id: refund.payer_id().to_string(),
+            payer_note: refund.payer_note().map(|s| s.to_string()),
+            payer_metadata: refund.payer_metadata().as_hex().to_string(),
+            paths,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use crate::channel::ChannelPolicy;
+use crate::node::Node;
+use crate::offer_details::ShortChannelId;
+use bitcoin::Network;
+use serde::Serialize;
+
+/// Block 840,000 was mined at 2024-04-20 00:09:27 UTC.
+const REFERENCE_BLOCK_HEIGHT: u32 = 840_000;
+const REFERENCE_BLOCK_TIMESTAMP: i64 = 1_713_571_767;
+const BLOCK_INTERVAL_SECS: i64 = 600;
+/// Tolerance of the block height estimate, about a week.
+const ESTIMATE_TOLERANCE_BLOCKS: u32 = 1_008;
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize)]
+pub enum ChannelKind {
+    /// The channel is announced in the graph.
+    Public,
+    /// The channel is not announced, but the SCID points to a plausible funding transaction.
+    Unannounced,
+    /// The SCID points to a block which did not exist when the invoice was created.
+    Alias,
+}
+
+/// A hop of a BOLT11 route hint with its channel checked against the graph.
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct Hop {
+    pub node: Node,
+    pub short_channel_id: ShortChannelId,
+    pub base_fee_msat: u32,
+    pub fee_proportional_millionths: u32,
+    pub cltv_expiry_delta: u16,
+    pub htlc_minimum_msat: Option<u64>,
+    pub htlc_maximum_msat: Option<u64>,
+    /// `None` if the graph database has no channels.
+    pub channel: Option<ChannelKind>,
+    /// Blocks between the funding transaction and the invoice creation.
+    pub channel_age: Option<u32>,
+    /// Policy the node announced for the channel, to compare with the route hint.
+    pub public_policy: Option<ChannelPolicy>,
+}
+
+impl Hop {
+    pub(crate) fn new(
+        node: Node,
+        hop: &lightning_invoice::RouteHintHop,
+        is_public: Option<bool>,
+        public_policy: Option<ChannelPolicy>,
+        created_at: i64,
+        network: Network,
+    ) -> Self {
+        let short_channel_id = ShortChannelId::from(hop.short_channel_id);
+        let tip = estimate_block_height(created_at, network);
+        let is_future =
+            tip.is_some_and(|tip| short_channel_id.block_height > tip + ESTIMATE_TOLERANCE_BLOCKS);
+        let channel = is_public.map(|is_public| match is_public {
+            true => ChannelKind::Public,
+            false if is_future => ChannelKind::Alias,
+            false => ChannelKind::Unannounced,
+        });
+        // Fedimint gateways put a federation index into an SCID pointing to the genesis block.
+        let is_fake = is_future || short_channel_id.block_height == 0;
+        let channel_age = tip
+            .filter(|_| !is_fake)
+            .map(|tip| tip.saturating_sub(short_channel_id.block_height));
+
+        Self {
+            node,
+            short_channel_id,
+            base_fee_msat: hop.fees.base_msat,
+            fee_proportional_millionths: hop.fees.proportional_millionths,
+            cltv_expiry_delta: hop.cltv_expiry_delta,
+            htlc_minimum_msat: hop.htlc_minimum_msat,
+            htlc_maximum_msat: hop.htlc_maximum_msat,
+            channel,
+            channel_age,
+            public_policy,
+        }
+    }
+}
+
+/// Estimates the chain tip at the given unix timestamp from the average block interval.
+///
+/// Test chains have irregular block intervals, e.g. Mutinynet is a signet with 30 second
+/// blocks, so only mainnet heights are estimated.
+pub(crate) fn estimate_block_height(timestamp: i64, network: Network) -> Option<u32> {
+    if network != Network::Bitcoin {
+        return None;
+    }
+    let blocks = (timestamp - REFERENCE_BLOCK_TIMESTAMP) / BLOCK_INTERVAL_SECS;
+    Some((REFERENCE_BLOCK_HEIGHT as i64 + blocks).max(0) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lightning_invoice::{RouteHintHop, RoutingFees};
+
+    #[test]
+    fn test_channel_kind() {
+        let node = Node {
+            pubkey: "node".to_string(),
+            ..Default::default()
+        };
+        let hop = |block_height: u64| RouteHintHop {
+            src_node_id: "03864ef025fde8fb587d989186ce6a4a186895ee44a926bfc370e2c366597a3f8f"
+                .parse()
+                .unwrap(),
+            short_channel_id: block_height << 40 | 1 << 16 | 1,
+            fees: RoutingFees {
+                base_msat: 1_000,
+                proportional_millionths: 100,
+            },
+            cltv_expiry_delta: 144,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: None,
+        };
+        let created_at = REFERENCE_BLOCK_TIMESTAMP + 144 * BLOCK_INTERVAL_SECS;
+        let network = Network::Bitcoin;
+
+        let public = Hop::new(
+            node.clone(),
+            &hop(840_000),
+            Some(true),
+            None,
+            created_at,
+            network,
+        );
+        assert_eq!(public.channel, Some(ChannelKind::Public));
+        assert_eq!(public.channel_age, Some(144));
+        assert_eq!(public.short_channel_id.to_string(), "840000x1x1");
+
+        let unannounced = Hop::new(
+            node.clone(),
+            &hop(839_000),
+            Some(false),
+            None,
+            created_at,
+            network,
+        );
+        assert_eq!(unannounced.channel, Some(ChannelKind::Unannounced));
+
+        let alias = Hop::new(
+            node.clone(),
+            &hop(16_000_000),
+            Some(false),
+            None,
+            created_at,
+            network,
+        );
+        assert_eq!(alias.channel, Some(ChannelKind::Alias));
+        assert_eq!(alias.channel_age, None);
+
+        let genesis = Hop::new(
+            node.clone(),
+            &hop(0),
+            Some(false),
+            None,
+            created_at,
+            network,
+        );
+        assert_eq!(genesis.channel_age, None);
+
+        let unknown = Hop::new(node, &hop(840_000), None, None, created_at, network);
+        assert_eq!(unknown.channel, None);
+        assert_eq!(unknown.channel_age, Some(144));
+    }
+
+    #[test]
+    fn test_channel_kind_off_mainnet() {
+        let node = Node {
+            pubkey: "node".to_string(),
+            ..Default::default()
+        };
+        let hop = RouteHintHop {
+            src_node_id: "03864ef025fde8fb587d989186ce6a4a186895ee44a926bfc370e2c366597a3f8f"
+                .parse()
+                .unwrap(),
+            short_channel_id: 2_900_000 << 40 | 1 << 16 | 1,
+            fees: RoutingFees {
+                base_msat: 1_000,
+                proportional_millionths: 100,
+            },
+            cltv_expiry_delta: 144,
+            htlc_minimum_msat: None,
+            htlc_maximum_msat: None,
+        };
+        let created_at = REFERENCE_BLOCK_TIMESTAMP;
+
+        // Testnet is far ahead of mainnet, its real channels are not SCID aliases.
+        for network in [Network::Testnet, Network::Regtest] {
+            let hop = Hop::new(node.clone(), &hop, Some(false), None, created_at, network);
+            assert_eq!(hop.channel, Some(ChannelKind::Unannounced));
+            assert_eq!(hop.channel_age, None);
+        }
+    }
+}
@@ -23,7 +23,33 @@ fn invoice(invoice: &str) -> Result<Template, String> {
         }
     };
 
+    let Some(details) = findings.details else {
+        let error = "Invoice details are missing";
+        return Ok(Template::render("invoice", context! { invoice, error }));
+    };
+
+    let confidence = findings.confidence;
+    let software = findings
+        .fingerprint
+        .and_then(|fingerprint| fingerprint.software.map(|software| (software, fingerprint)))
+        .map(|(software, fingerprint)| {
+            format!(
+                "{software} ({:?} confidence: {})",
+                fingerprint.confidence,
+                fingerprint.signals.join(", ")
+            )
+        });
+    let evidences = findings
+        .evidences
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
     let recipient = findings.recipient;
+    let provider = recipient.provider().cloned();
+    let peer = match &recipient {
+        RecipientNode::PublicRoutingNode { lsp, .. } => Some(lsp.name.clone()),
+        _ => None,
+    };
     let payee = findings.payee;
     let route_hints = findings.route_hints;
 
@@ -46,20 +72,104 @@ fn invoice(invoice: &str) -> Result<Template, String> {
             lsp.name,
             String::new(),
         ),
+        RecipientNode::Swap { swap_service } => (
+            "Payment will be swapped to chain via",
+            "",
+            swap_service.name,
+            String::new(),
+        ),
+        RecipientNode::UnknownSwap { .. } => (
+            "Payment may be swapped to chain via",
+            "",
+            "an unknown swap service".to_string(),
+            String::new(),
+        ),
+        RecipientNode::PublicRoutingNode { id, alias, .. } => {
+            ("Public routing node", "", alias.unwrap_or_default(), id)
+        }
+        RecipientNode::PhantomNode {
+            id,
+            real_nodes,
+            provider,
+        } => {
+            let name = match provider {
+                Some(provider) => provider.name,
+                None => real_nodes
+                    .iter()
+                    .map(|node| node.alias.clone().unwrap_or_else(|| node.pubkey.clone()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            ("Phantom node served by", "", name, id)
+        }
+        RecipientNode::EcashGateway {
+            pubkey,
+            alias,
+            federation_index,
+            ..
+        } => (
+            "Custodial",
+            "Ecash mint behind Fedimint gateway",
+            format!(
+                "federation {federation_index} of {}",
+                alias.unwrap_or(pubkey)
+            ),
+            String::new(),
+        ),
+        RecipientNode::SharedEntryNode { pubkey, alias, .. } => (
+            "Unknown provider behind node",
+            "",
+            alias.unwrap_or_default(),
+            pubkey,
+        ),
+        RecipientNode::Ambiguous { candidates } => {
+            let names = candidates
+                .iter()
+                .filter_map(RecipientNode::provider)
+                .map(|provider| provider.name.clone())
+                .collect::<Vec<_>>()
+                .join(" or ");
+            ("Ambiguous", "", names, String::new())
+        }
         RecipientNode::Unknown => ("Unknown", "", String::new(), String::new()),
     };
 
-    let amount = format_msat(findings.details.amount_msat);
-    let description = findings.details.description;
-    let network = findings.details.network;
+    let amount = format_msat(details.amount_msat);
+    let description = details.description;
+    let description_hash = details.description_hash;
+    let network = details.network;
+    let created_at = details.created_at.to_rfc2822();
+    let expires_at = details.expires_at.map(|d| d.to_rfc2822());
+    let is_expired = details.is_expired;
+    let payment_hash = details.payment_hash;
+    let has_payment_secret = details.has_payment_secret;
+    let min_final_cltv_expiry_delta = details.min_final_cltv_expiry_delta;
+    let payment_metadata = details.payment_metadata;
+    let feature_bits = details.feature_bits;
+    let fallback_addresses = details.fallback_addresses;
 
-    let mempool_space_base_url = "https://mempool.space/lightning/node";
+    let mempool_space_base_url = mempool_space_base_url(network);
     Ok(Template::render(
         "invoice",
-        context! { amount, network, description, invoice, mempool_space_base_url, route_hints, payee, custody, service, name, id },
+        context! {
+            amount, network, description, description_hash, created_at, expires_at, is_expired,
+            payment_hash, has_payment_secret, min_final_cltv_expiry_delta, payment_metadata,
+            feature_bits, fallback_addresses,
+            invoice, mempool_space_base_url, route_hints, payee, custody, service, name, id, peer, provider, confidence, software, evidences
+        },
     ))
 }
 
+/// Explorer of lightning nodes of the network, `None` if mempool.space has none.
+fn mempool_space_base_url(network: &str) -> Option<&'static str> {
+    match network {
+        "Mainnet" => Some("https://mempool.space/lightning/node"),
+        "Testnet" => Some("https://mempool.space/testnet/lightning/node"),
+        "Signet" => Some("https://mempool.space/signet/lightning/node"),
+        _ => None,
+    }
+}
+
 fn format_msat(msat: Option<u64>) -> String {
     match msat {
         None => String::new(),
@@ -93,5 +203,7 @@ fn format_service_kind(service: &ServiceKind) -> &'static str {
         ServiceKind::ConsumerWallet => "Consumer wallet",
         ServiceKind::Exchange => "Exchange",
         ServiceKind::Lsp => "LSP",
+        ServiceKind::SwapService => "Swap service",
+        ServiceKind::EcashMint => "Ecash mint",
     }
 }